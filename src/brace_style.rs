@@ -0,0 +1,32 @@
+/// Where [CodeBuffer::block()](crate::CodeBuffer::block) places the opening
+/// brace of a block.
+///
+/// # Example
+/// ```
+/// use simplegen::{BraceStyle, CodeBuffer};
+///
+/// let mut buffer = CodeBuffer::default();
+/// buffer.set_brace_style(BraceStyle::Allman);
+///
+/// {
+///     let mut block = buffer.block("if (x)");
+///     block.println("y();");
+/// }
+///
+/// assert_eq!("if (x)\n{\n    y();\n}", buffer.to_string());
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum BraceStyle {
+    /// The opening brace ends the header line, `if (x) {`.
+    KAndR,
+    /// The opening brace is on a line of its own, below the header.
+    Allman,
+}
+
+impl Default for BraceStyle {
+    /// The default brace style is K&R.
+    fn default() -> Self {
+        BraceStyle::KAndR
+    }
+}
@@ -0,0 +1,40 @@
+use alloc::string::String;
+
+/// A [Checkpoint] records the state of a [CodeBuffer](crate::CodeBuffer), so
+/// everything written after it can be discarded.
+///
+/// It's created by calling
+/// [CodeBuffer::checkpoint()](crate::CodeBuffer::checkpoint) and used by
+/// [CodeBuffer::rollback()](crate::CodeBuffer::rollback).
+///
+/// # Example
+/// ```
+/// use simplegen::CodeBuffer;
+///
+/// let mut buffer = CodeBuffer::default();
+/// buffer.println("fn main() {");
+///
+/// let checkpoint = buffer.checkpoint();
+/// buffer.println_right("unfinished(");
+/// buffer.rollback(checkpoint);
+///
+/// buffer.println_right("finished();");
+/// buffer.println_left("}");
+///
+/// assert_eq!("fn main() {\n    finished();\n}", buffer.to_string());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Checkpoint {
+    /// Number of lines in the buffer when the checkpoint was made.
+    pub(crate) line_count: usize,
+    /// Indentation level of the buffer when the checkpoint was made.
+    pub(crate) level: u32,
+    /// Line started by `print()` that hadn't been ended when the checkpoint
+    /// was made.
+    pub(crate) pending: Option<String>,
+    /// Number of levels saved by `push_level()` when the checkpoint was made.
+    pub(crate) level_stack_len: usize,
+    /// Number of strings written by `println_once()` when the checkpoint was
+    /// made.
+    pub(crate) written_once_len: usize,
+}
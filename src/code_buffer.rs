@@ -1,8 +1,43 @@
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
+use core::str;
+#[cfg(feature = "std")]
+use std::collections::HashMap;
+#[cfg(feature = "std")]
+use std::fs::File;
+#[cfg(feature = "std")]
+use std::io::{self, Write};
+#[cfg(feature = "std")]
+use std::path::Path;
+#[cfg(feature = "rustfmt")]
+use std::process::{Command, Stdio};
+
+use crate::diff::diff_lines;
+use crate::escape::escape_xml_attr;
+use crate::pad::pad_right;
+#[cfg(feature = "std")]
+use crate::template::fill_placeholders;
+use crate::template::render_template;
+use crate::width::text_width;
+use crate::wrap::{wrap_words, wrap_words_hanging};
+use crate::{
+    BlockGuard, BraceStyle, Checkpoint, CodeBufferBuilder, CodeSink, CounterList, DiffLine,
+    ElementGuard, EmptyBufferError, IndentGuard, IndentStyle, LineEnding, ListBuilder,
+    MatchBuilder, RegionGuard, SwitchBuilder, Table, TemplateContext, UnbalancedIndentError,
+};
+
+/// Default maximum number of columns a line is indented by.
+const DEFAULT_MAX_INDENT_COLUMNS: usize = 4096;
+
 /// A [CodeBuffer] is a simple tool that's useful for basic code generation.
 ///
 /// It's a text buffer that maintains indentation level when writing new lines.
 /// You retrieve the correctly indented contents of the buffer by calling
-/// [CodeBuffer::to_string()].
+/// `to_string()`.
 ///
 /// # Example
 /// ```
@@ -23,34 +58,93 @@
 /// //     x + 1
 /// // }
 /// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct CodeBuffer {
     /// Vector of lines of code. The whitespace at the start of each line is
     /// preserved here.
     buffer: Vec<String>,
-    /// Number of spaces to indent code by.
-    indent: i32,
+    /// Whitespace to indent code by for each level.
+    style: IndentStyle,
     /// Level of indentation of the current line of code.
-    level: i32,
+    level: u32,
+    /// Number of levels every indented line is shifted right by, on top of
+    /// `level`.
+    base_level: u32,
+    /// Separator placed between lines by `to_string()`.
+    line_ending: LineEnding,
+    /// Whether `to_string()` ends the last line with a line ending.
+    trailing_newline: bool,
+    /// Where [CodeBuffer::block()] places the opening brace.
+    brace_style: BraceStyle,
+    /// Whether `to_string()` trims trailing spaces and tabs from
+    /// each line.
+    trim_trailing_whitespace: bool,
+    /// Whether [CodeBuffer::println()] indents lines that are blank, rather
+    /// than writing them as empty lines.
+    indent_blank_lines: bool,
+    /// Maximum number of blank lines that can be written in a row, if any.
+    max_consecutive_blanks: Option<usize>,
+    /// Whether [CodeBuffer::println()] panics if it's given a string that
+    /// contains a newline.
+    strict_single_line: bool,
+    /// Lines written before the contents of the buffer in the output.
+    preamble: Vec<String>,
+    /// Lines written after the contents of the buffer in the output.
+    epilogue: Vec<String>,
+    /// Token written before the text of comments.
+    comment_prefix: String,
+    /// Token written at the start of each line inside a block comment by
+    /// [CodeBuffer::wrap_in_block_comment()].
+    block_comment_line_prefix: String,
+    /// Templates for the opening and closing markers written by
+    /// [CodeBuffer::region()]. `{name}` is replaced by the region's name.
+    region_markers: (String, String),
+    /// Tokens written before and after the body of annotations by
+    /// [CodeBuffer::annotation()].
+    annotation_style: (String, String),
+    /// Width that [CodeBuffer::println()] wraps lines at, if any.
+    max_width: Option<usize>,
+    /// Maximum number of columns a line is indented by, however deep the
+    /// indentation level is.
+    max_indent_columns: usize,
+    /// Whether lines are written without any indentation, set by
+    /// [CodeBuffer::with_no_indent()].
+    no_indent: bool,
+    /// Line started by [CodeBuffer::print()] or [fmt::Write] that hasn't been
+    /// ended yet. The indentation is applied when the line is started.
+    pending: Option<String>,
+    /// Bytes written through [io::Write] that end part way through a UTF-8
+    /// character, kept until the rest of the character arrives.
+    incomplete_utf8: Vec<u8>,
+    /// Indentation level recorded for each position returned by
+    /// [CodeBuffer::mark()].
+    marks: BTreeMap<usize, u32>,
+    /// Indentation levels saved by [CodeBuffer::push_level()].
+    level_stack: Vec<u32>,
+    /// Strings already written by [CodeBuffer::println_once()].
+    /// Each string is kept with the number of strings written before it, so
+    /// the ones written after a checkpoint can be forgotten by a rollback.
+    written_once: BTreeMap<String, usize>,
+    /// Number of lines already written out by [CodeBuffer::drain_to_writer()].
+    drained_lines: usize,
+    /// Number of blank lines at the end of the lines already written out by
+    /// [CodeBuffer::drain_to_writer()].
+    drained_blanks: usize,
 }
 
 impl Default for CodeBuffer {
     /// Create a default implementation of the `CodeBuffer` with an
     /// indentation level of 4 spaces.
     fn default() -> Self {
-        let buffer: Vec<String> = Vec::new();
-        let indent = 4;
-        let level = 0;
-        CodeBuffer {
-            buffer,
-            indent,
-            level,
-        }
+        CodeBuffer::with_style(IndentStyle::default())
     }
 }
 
-impl ToString for CodeBuffer {
-    /// Retrieve a string of the internal state of the printer. This will be a
-    /// string that has been formatted with correct indentation levels
+impl fmt::Display for CodeBuffer {
+    /// Write the internal state of the printer to a formatter. This will be a
+    /// string that has been formatted with correct indentation levels, and is
+    /// also what `to_string()` returns.
     ///
     /// # Examples
     ///
@@ -74,8 +168,134 @@ impl ToString for CodeBuffer {
     /// // a single string.
     /// println!("{}", buffer.to_string());
     /// ```
-    fn to_string(&self) -> String {
-        self.buffer.join("\n")
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let separator = self.line_ending.as_str();
+        let mut lines = self.output_lines().peekable();
+        let has_lines = lines.peek().is_some();
+
+        if let Some(first) = lines.next() {
+            f.write_str(first)?;
+        }
+        for line in lines {
+            f.write_str(separator)?;
+            f.write_str(line)?;
+        }
+
+        if self.trailing_newline && has_lines {
+            f.write_str(separator)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Write for CodeBuffer {
+    /// Write a string to the buffer, so the `write!` and `writeln!` macros can
+    /// be used with a `CodeBuffer`.
+    ///
+    /// Text is appended to the current line, which is indented when it is
+    /// started. Each `\n` ends the current line.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use simplegen::CodeBuffer;
+    /// use std::fmt::Write;
+    ///
+    /// let mut buffer = CodeBuffer::default();
+    ///
+    /// writeln!(buffer, "fn {}() {{", "main").unwrap();
+    /// buffer.indent_right();
+    /// write!(buffer, "println!(").unwrap();
+    /// writeln!(buffer, "\"Hello, World!\");").unwrap();
+    /// buffer.indent_left();
+    /// writeln!(buffer, "}}").unwrap();
+    ///
+    /// assert_eq!(
+    ///     "fn main() {\n    println!(\"Hello, World!\");\n}",
+    ///     buffer.to_string()
+    /// );
+    /// ```
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        let mut segments = s.split('\n');
+        // The last segment is never followed by a newline, so it stays pending.
+        let last = segments.next_back().unwrap_or_default();
+
+        for segment in segments {
+            self.pending_line().push_str(segment);
+            self.commit_pending();
+        }
+
+        if !last.is_empty() {
+            self.pending_line().push_str(last);
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(feature = "std")]
+impl io::Write for CodeBuffer {
+    /// Write UTF-8 bytes to the buffer, so a `CodeBuffer` can be used with
+    /// APIs that write to an [io::Write].
+    ///
+    /// This behaves like the [fmt::Write] implementation, ending a line at each
+    /// `\n`. A UTF-8 character split across two writes is kept until the rest
+    /// of it arrives. Invalid UTF-8 returns an error of kind
+    /// [io::ErrorKind::InvalidData].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use simplegen::CodeBuffer;
+    /// use std::io::Write;
+    ///
+    /// let mut buffer = CodeBuffer::default();
+    /// buffer.indent_right();
+    ///
+    /// buffer.write_all(b"first\nsecond\n").unwrap();
+    ///
+    /// assert_eq!("    first\n    second", buffer.to_string());
+    /// ```
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let start = self.incomplete_utf8.len();
+        self.incomplete_utf8.extend_from_slice(buf);
+
+        let valid = match str::from_utf8(&self.incomplete_utf8) {
+            Ok(text) => text.len(),
+            // The bytes end part way through a character.
+            Err(error) if error.error_len().is_none() => error.valid_up_to(),
+            Err(error) => {
+                self.incomplete_utf8.truncate(start);
+                return Err(io::Error::new(io::ErrorKind::InvalidData, error));
+            }
+        };
+
+        let rest = self.incomplete_utf8.split_off(valid);
+        let bytes = core::mem::replace(&mut self.incomplete_utf8, rest);
+        let text = String::from_utf8(bytes).expect("bytes were validated as UTF-8");
+        // Writing to a CodeBuffer never fails.
+        fmt::Write::write_str(self, &text).unwrap();
+
+        Ok(buf.len())
+    }
+
+    /// Flushing does nothing, as everything is already in memory.
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl CodeSink for CodeBuffer {
+    fn println(&mut self, str: &str) -> &mut Self {
+        CodeBuffer::println(self, str)
+    }
+
+    fn indent_right(&mut self) -> &mut Self {
+        CodeBuffer::indent_right(self)
+    }
+
+    fn indent_left(&mut self) -> &mut Self {
+        CodeBuffer::indent_left(self)
     }
 }
 
@@ -93,109 +313,306 @@ impl CodeBuffer {
     ///
     /// let mut buffer = CodeBuffer::new(4);
     /// ```
-    pub fn new(indent: i32) -> Self {
+    pub fn new(indent: u32) -> Self {
+        CodeBuffer::with_style(IndentStyle::Spaces(indent))
+    }
+
+    /// Create a new `CodeBuffer` with room for `lines` lines reserved up
+    /// front, to avoid reallocating as lines are written.
+    ///
+    /// # Arguments
+    ///
+    /// * `indent` - Number of spaces to indent by.
+    /// * `lines` - Number of lines to reserve room for.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use simplegen::CodeBuffer;
+    ///
+    /// let mut buffer = CodeBuffer::with_capacity(4, 10_000);
+    /// ```
+    pub fn with_capacity(indent: u32, lines: usize) -> Self {
+        let mut buffer = CodeBuffer::new(indent);
+        buffer.buffer.reserve(lines);
+        buffer
+    }
+
+    /// Create a new `CodeBuffer` that indents with the given [IndentStyle].
+    ///
+    /// # Arguments
+    ///
+    /// * `style` - Whitespace to indent by for each level.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use simplegen::{CodeBuffer, IndentStyle};
+    ///
+    /// // Each level of indentation is a single tab character.
+    /// let mut buffer = CodeBuffer::with_style(IndentStyle::Tabs);
+    /// ```
+    pub fn with_style(style: IndentStyle) -> Self {
         let buffer: Vec<String> = Vec::new();
         let level = 0;
+        let base_level = 0;
+        let line_ending = LineEnding::default();
+        let trailing_newline = false;
+        let brace_style = BraceStyle::default();
+        let trim_trailing_whitespace = false;
+        let indent_blank_lines = false;
+        let max_consecutive_blanks = None;
+        let strict_single_line = false;
+        let preamble = Vec::new();
+        let epilogue = Vec::new();
+        let comment_prefix = "//".to_string();
+        let block_comment_line_prefix = String::new();
+        let region_markers = ("// region: {name}".to_string(), "// endregion".to_string());
+        let annotation_style = ("#[".to_string(), "]".to_string());
+        let max_width = None;
+        let max_indent_columns = DEFAULT_MAX_INDENT_COLUMNS;
+        let no_indent = false;
+        let pending = None;
+        let incomplete_utf8 = Vec::new();
+        let marks = BTreeMap::new();
+        let level_stack = Vec::new();
+        let written_once = BTreeMap::new();
+        let drained_lines = 0;
+        let drained_blanks = 0;
         CodeBuffer {
             buffer,
-            indent,
+            style,
             level,
+            base_level,
+            line_ending,
+            trailing_newline,
+            brace_style,
+            trim_trailing_whitespace,
+            indent_blank_lines,
+            max_consecutive_blanks,
+            strict_single_line,
+            preamble,
+            epilogue,
+            comment_prefix,
+            block_comment_line_prefix,
+            region_markers,
+            annotation_style,
+            max_width,
+            max_indent_columns,
+            no_indent,
+            pending,
+            incomplete_utf8,
+            marks,
+            level_stack,
+            written_once,
+            drained_lines,
+            drained_blanks,
         }
     }
 
-    /// Write a line to the internal buffer at the current indentation level.
+    /// Create a new `CodeBuffer` that indents each level by repeating `unit`.
     ///
     /// # Arguments
     ///
-    /// * `str` - String to append to the buffer.
+    /// * `unit` - String to write for each level of indentation.
     ///
     /// # Examples
     ///
     /// ```
     /// use simplegen::CodeBuffer;
     ///
-    /// let mut buffer = CodeBuffer::default();
+    /// let mut buffer = CodeBuffer::with_indent_unit(". ".to_string());
+    /// buffer.println("root");
+    /// buffer.println_right("child");
+    /// buffer.println_right("grandchild");
     ///
-    /// // Calling this will append "Hello, World!" to the buffer at the current
-    /// // indentation level, in this case an indentation level of 0.
-    /// buffer.println("Hello, World!");
+    /// assert_eq!("root\n. child\n. . grandchild", buffer.to_string());
     /// ```
-    pub fn println(&mut self, str: &str) {
-        let indent_size = self.indent * self.level;
-        let indent_str = " ".repeat(indent_size as usize);
-        self.buffer.push(format!("{}{}", indent_str, str));
+    pub fn with_indent_unit(unit: String) -> Self {
+        CodeBuffer::with_style(IndentStyle::Unit(unit))
     }
 
-    /// Indent the internal buffer right.
+    /// Create a [CodeBufferBuilder] to configure a new `CodeBuffer`.
     ///
     /// # Examples
     ///
     /// ```
     /// use simplegen::CodeBuffer;
-    /// let mut buffer = CodeBuffer::default();
     ///
-    /// // Increases indentation level by one.
-    /// buffer.indent_right();
+    /// let mut buffer = CodeBuffer::builder().indent(2).trailing_newline(true).build();
+    /// ```
+    pub fn builder() -> CodeBufferBuilder {
+        CodeBufferBuilder::new()
+    }
+
+    /// Create a new `CodeBuffer` with the default settings, filled with the
+    /// given lines.
     ///
-    /// buffer.println("Hello, World!");
-    /// // The string "    Hello, World!" gets appended to the buffer, as we
-    /// // have increased indentation level by one, and default indentation
-    /// // level is 4 spaces.
+    /// # Arguments
+    ///
+    /// * `lines` - Lines to fill the buffer with.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use simplegen::CodeBuffer;
+    ///
+    /// let buffer = CodeBuffer::from_lines(vec!["first", "second"]);
+    ///
+    /// assert_eq!("first\nsecond", buffer.to_string());
     /// ```
-    pub fn indent_right(&mut self) {
-        self.level += 1;
+    pub fn from_lines<I, S>(lines: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let mut buffer = CodeBuffer::default();
+        buffer.extend(lines);
+        buffer
     }
 
-    /// Indent the internal buffer left.
+    /// Create a new `CodeBuffer` indented by `indent` spaces, filled with the
+    /// lines of existing source code. The indentation level of each line is
+    /// inferred from its leading spaces, so the source can be edited and
+    /// written back out with its structure preserved.
+    ///
+    /// Lines whose leading spaces aren't a multiple of `indent`, such as
+    /// aligned continuation lines, are preserved verbatim and don't change the
+    /// indentation level. The current indentation level is left at the level
+    /// of the last line whose level could be inferred, so lines written
+    /// afterwards nest consistently with the source.
+    ///
+    /// # Arguments
+    ///
+    /// * `src` - Source code to fill the buffer with.
+    /// * `indent` - Number of spaces per level of indentation in `src`.
     ///
     /// # Examples
     ///
     /// ```
     /// use simplegen::CodeBuffer;
-    /// let mut buffer = CodeBuffer::default();
     ///
-    /// // We set the indentation level to 1 for demonstration purposes.
+    /// let mut buffer = CodeBuffer::from_str_inferring("fn main() {\n    let x = 1;", 4);
+    /// buffer.println("let y = 2;");
+    /// buffer.println_left("}");
+    ///
+    /// assert_eq!(
+    ///     "fn main() {\n    let x = 1;\n    let y = 2;\n}",
+    ///     buffer.to_string()
+    /// );
+    /// ```
+    pub fn from_str_inferring(src: &str, indent: u32) -> Self {
+        let mut buffer = CodeBuffer::new(indent);
+        for line in split_text_lines(src) {
+            let content = line.trim_start_matches(' ');
+            let spaces = (line.len() - content.len()) as u32;
+            if content.is_empty() {
+                buffer.buffer.push(String::new());
+            } else if indent > 0 && spaces % indent == 0 {
+                let level = spaces / indent;
+                buffer.buffer.push(buffer.indent_line_at(level, content));
+                buffer.level = level;
+            } else {
+                buffer.buffer.push(line);
+            }
+        }
+        buffer
+    }
+
+    /// Get the current indentation level.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use simplegen::CodeBuffer;
+    ///
+    /// let mut buffer = CodeBuffer::default();
     /// buffer.indent_right();
     ///
-    /// buffer.indent_left();
-    /// // The indentation level has returned to 0.
+    /// assert_eq!(1, buffer.level());
+    /// ```
+    pub fn level(&self) -> u32 {
+        self.level
+    }
+
+    /// Get the number of characters written for each level of indentation.
+    /// This is the number of spaces for [IndentStyle::Spaces], 1 for
+    /// [IndentStyle::Tabs], and the number of characters in the unit for
+    /// [IndentStyle::Unit].
+    ///
+    /// # Examples
     ///
-    /// buffer.println("Hello, World!");
-    /// // The string "Hello, World!" is appended to the buffer, as we set the
-    /// // indentation level from 1, to 0.
     /// ```
-    pub fn indent_left(&mut self) {
-        if self.level > 0 {
-            self.level -= 1;
-        }
+    /// use simplegen::CodeBuffer;
+    ///
+    /// let buffer = CodeBuffer::new(2);
+    ///
+    /// assert_eq!(2, buffer.indent_width());
+    /// ```
+    pub fn indent_width(&self) -> u32 {
+        self.style.unit_width()
     }
 
-    /// Indent right then print a string to the internal buffer.
+    /// Iterate over the lines in the buffer. Each line includes its
+    /// indentation. A line started with `write!` isn't included until it has
+    /// been ended.
     ///
-    /// # Arguments
+    /// # Examples
     ///
-    /// * `str` - String to append to the buffer.
+    /// ```
+    /// use simplegen::CodeBuffer;
+    ///
+    /// let mut buffer = CodeBuffer::default();
+    /// buffer.println("{");
+    /// buffer.println_right("\"number\": 1");
+    /// buffer.println_left("}");
+    ///
+    /// let longest = buffer.lines().map(str::len).max();
+    ///
+    /// assert_eq!(Some(15), longest);
+    /// ```
+    pub fn lines(&self) -> impl Iterator<Item = &str> {
+        self.buffer.iter().map(String::as_str)
+    }
+
+    /// Get the number of lines in the buffer.
     ///
     /// # Examples
+    ///
     /// ```
     /// use simplegen::CodeBuffer;
     ///
     /// let mut buffer = CodeBuffer::default();
-    /// buffer.println_right("Hello, World!");
-    /// // The string "    Hello, World!" is appended to the buffer, as the
-    /// // CodeBuffer::println_right() function will first increment the
-    /// // indentation level, then append the indented string to the buffer.
+    /// buffer.println("first");
+    /// buffer.println("second");
+    ///
+    /// assert_eq!(2, buffer.line_count());
     /// ```
-    pub fn println_right(&mut self, str: &str) {
-        self.indent_right();
-        self.println(str);
+    pub fn line_count(&self) -> usize {
+        self.buffer.len()
     }
 
-    /// Indent left then print a string to the internal buffer.
+    /// Get the line most recently written to the buffer, including its
+    /// indentation.
     ///
-    /// # Arguments
+    /// # Examples
     ///
-    /// * `str` - String to append to the buffer.
+    /// ```
+    /// use simplegen::CodeBuffer;
+    ///
+    /// let mut buffer = CodeBuffer::default();
+    /// assert_eq!(None, buffer.last_line());
+    ///
+    /// buffer.println_right("x: 1");
+    /// assert_eq!(Some("    x: 1"), buffer.last_line());
+    /// ```
+    pub fn last_line(&self) -> Option<&str> {
+        self.buffer.last().map(String::as_str)
+    }
+
+    /// Get a mutable reference to the line most recently written to the
+    /// buffer, so it can be changed in place. The line already includes its
+    /// indentation.
     ///
     /// # Examples
     ///
@@ -203,51 +620,5708 @@ impl CodeBuffer {
     /// use simplegen::CodeBuffer;
     ///
     /// let mut buffer = CodeBuffer::default();
-    /// buffer.indent_right();
-    /// // Set the indentation level to 1.
     ///
-    /// buffer.println_left("Hello, World!");
-    /// // Indentation level will be returned to 0, so "Hello, World!" will be
-    /// // appended to the buffer.
+    /// for (index, item) in ["a", "b"].iter().enumerate() {
+    ///     if index > 0 {
+    ///         // Another item follows, so separate it from the last one.
+    ///         if let Some(line) = buffer.last_line_mut() {
+    ///             line.push(',');
+    ///         }
+    ///     }
+    ///     buffer.println(item);
+    /// }
+    ///
+    /// assert_eq!("a,\nb", buffer.to_string());
     /// ```
-    pub fn println_left(&mut self, str: &str) {
-        self.indent_left();
-        self.println(str);
+    pub fn last_line_mut(&mut self) -> Option<&mut String> {
+        self.buffer.last_mut()
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use crate::code_buffer::CodeBuffer;
+    /// Get the number of bytes in the string that `to_string()` returns,
+    /// without building the string.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use simplegen::CodeBuffer;
+    ///
+    /// let mut buffer = CodeBuffer::default();
+    /// buffer.println("{");
+    /// buffer.println_right("\"number\": 1");
+    /// buffer.println_left("}");
+    ///
+    /// assert_eq!(buffer.to_string().len(), buffer.byte_len());
+    /// ```
+    pub fn byte_len(&self) -> usize {
+        let separator = self.line_ending.as_str().len();
+        let mut line_count = 0;
+        let mut byte_len = 0;
 
-    #[test]
-    fn should_write_to_buffer() {
-        let mut printer = CodeBuffer::new(4);
-        printer.println("testing");
-        let actual = printer.to_string();
+        for line in self.output_lines() {
+            line_count += 1;
+            byte_len += line.len();
+        }
 
-        assert_eq!("testing", actual);
+        if line_count > 0 {
+            byte_len += separator * (line_count - 1);
+            if self.trailing_newline {
+                byte_len += separator;
+            }
+        }
+        byte_len
     }
 
-    #[test]
-    fn default_should_return_valid_printer() {
-        let mut printer = CodeBuffer::default();
-        printer.println("default");
-        let actual = printer.to_string();
+    /// Get the number of characters in the widest line in the buffer,
+    /// including its indentation. A tab counts as one character. This is 0 for
+    /// an empty buffer.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use simplegen::CodeBuffer;
+    ///
+    /// let mut buffer = CodeBuffer::default();
+    /// buffer.println("fn main() {");
+    /// buffer.println_right("run();");
+    /// buffer.println_left("}");
+    ///
+    /// assert_eq!(11, buffer.max_line_width());
+    /// ```
+    pub fn max_line_width(&self) -> usize {
+        self.lines().map(text_width).max().unwrap_or(0)
+    }
 
-        assert_eq!("default", actual);
+    /// Get the number of lines in the buffer. This is the same as
+    /// [CodeBuffer::line_count()].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use simplegen::CodeBuffer;
+    ///
+    /// let mut buffer = CodeBuffer::default();
+    /// buffer.println("Hello, World!");
+    ///
+    /// assert_eq!(1, buffer.len());
+    /// ```
+    pub fn len(&self) -> usize {
+        self.line_count()
     }
 
-    #[test]
-    fn should_indent_on_indent_right() {
-        // Don't use default as we are relying on the indentation being a
-        // particular length.
-        let mut printer = CodeBuffer::new(4);
-        printer.indent_right();
-        printer.println("testing");
-        let actual = printer.to_string();
+    /// Check whether the buffer has no lines in it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use simplegen::CodeBuffer;
+    ///
+    /// let mut buffer = CodeBuffer::default();
+    /// assert!(buffer.is_empty());
+    ///
+    /// buffer.println("Hello, World!");
+    /// assert!(!buffer.is_empty());
+    /// ```
+    pub fn is_empty(&self) -> bool {
+        self.buffer.is_empty()
+    }
 
-        assert_eq!("    testing", actual);
+    /// Set the number of spaces to indent by for each level. Lines already in
+    /// the buffer keep the indentation they were written with, so this only
+    /// affects lines written afterwards.
+    ///
+    /// # Arguments
+    ///
+    /// * `indent` - Number of spaces to indent by.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use simplegen::CodeBuffer;
+    ///
+    /// let mut buffer = CodeBuffer::new(2);
+    /// buffer.indent_right();
+    /// buffer.println("header");
+    ///
+    /// buffer.set_indent(4);
+    /// buffer.println("body");
+    ///
+    /// assert_eq!("  header\n    body", buffer.to_string());
+    /// ```
+    pub fn set_indent(&mut self, indent: u32) {
+        self.style = IndentStyle::Spaces(indent);
+    }
+
+    /// Set the separator that is placed between lines when the buffer is
+    /// converted to a string. The default is [LineEnding::Lf].
+    ///
+    /// # Arguments
+    ///
+    /// * `line_ending` - Line ending to join lines with.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use simplegen::{CodeBuffer, LineEnding};
+    ///
+    /// let mut buffer = CodeBuffer::default();
+    /// buffer.set_line_ending(LineEnding::Crlf);
+    ///
+    /// buffer.println("first");
+    /// buffer.println("second");
+    ///
+    /// assert_eq!("first\r\nsecond", buffer.to_string());
+    /// ```
+    pub fn set_line_ending(&mut self, line_ending: LineEnding) {
+        self.line_ending = line_ending;
+    }
+
+    /// Set where [CodeBuffer::block()] and [CodeBuffer::block_with_close()]
+    /// place the opening brace of a block. The default is
+    /// [BraceStyle::KAndR]. The closing line is always written at the
+    /// indentation level of the header.
+    ///
+    /// # Arguments
+    ///
+    /// * `brace_style` - Brace style to open blocks with.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use simplegen::{BraceStyle, CodeBuffer};
+    ///
+    /// let mut buffer = CodeBuffer::default();
+    /// buffer.set_brace_style(BraceStyle::Allman);
+    ///
+    /// buffer.block("void main()").println("return;");
+    ///
+    /// assert_eq!("void main()\n{\n    return;\n}", buffer.to_string());
+    /// ```
+    pub fn set_brace_style(&mut self, brace_style: BraceStyle) {
+        self.brace_style = brace_style;
+    }
+
+    /// Set whether the last line is followed by a line ending when the buffer
+    /// is converted to a string. This is off by default. An empty buffer is
+    /// always converted to an empty string.
+    ///
+    /// # Arguments
+    ///
+    /// * `trailing_newline` - Whether to end the last line with a line ending.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use simplegen::CodeBuffer;
+    ///
+    /// let mut buffer = CodeBuffer::default();
+    /// buffer.set_trailing_newline(true);
+    ///
+    /// buffer.println("Hello, World!");
+    ///
+    /// assert_eq!("Hello, World!\n", buffer.to_string());
+    /// ```
+    pub fn set_trailing_newline(&mut self, trailing_newline: bool) {
+        self.trailing_newline = trailing_newline;
+    }
+
+    /// Set whether trailing spaces and tabs are trimmed from each line when the
+    /// buffer is converted to a string. This is off by default. Leading
+    /// indentation and whitespace inside a line are left untouched.
+    ///
+    /// # Arguments
+    ///
+    /// * `trim` - Whether to trim trailing whitespace from each line.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use simplegen::CodeBuffer;
+    ///
+    /// let mut buffer = CodeBuffer::default();
+    /// buffer.set_trim_trailing_whitespace(true);
+    ///
+    /// buffer.println("let x = 1;   ");
+    /// buffer.println_right("");
+    ///
+    /// assert_eq!("let x = 1;\n", buffer.to_string());
+    /// ```
+    pub fn set_trim_trailing_whitespace(&mut self, trim: bool) {
+        self.trim_trailing_whitespace = trim;
+    }
+
+    /// Set whether [CodeBuffer::println()] indents blank lines. By default, a
+    /// line that is empty or only contains whitespace is written as an empty
+    /// line, so it never contains trailing whitespace. Turn this on to write
+    /// blank lines with their indentation, as older versions did.
+    ///
+    /// # Arguments
+    ///
+    /// * `indent` - Whether to indent blank lines.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use simplegen::CodeBuffer;
+    ///
+    /// let mut buffer = CodeBuffer::new(2);
+    /// buffer.set_indent_blank_lines(true);
+    ///
+    /// buffer.println_right("");
+    ///
+    /// assert_eq!("  ", buffer.to_string());
+    /// ```
+    pub fn set_indent_blank_lines(&mut self, indent: bool) {
+        self.indent_blank_lines = indent;
+    }
+
+    /// Set the maximum number of blank lines that can be written in a row.
+    /// Blank lines written by [CodeBuffer::println()],
+    /// [CodeBuffer::blank_line()] and [CodeBuffer::println_raw()] past the
+    /// limit are dropped. This is `None`, for no limit, by default.
+    ///
+    /// # Arguments
+    ///
+    /// * `max` - Maximum number of consecutive blank lines, if any.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use simplegen::CodeBuffer;
+    ///
+    /// let mut buffer = CodeBuffer::default();
+    /// buffer.set_max_consecutive_blanks(Some(1));
+    ///
+    /// buffer.println("fn first() {}");
+    /// buffer.blank_line();
+    /// buffer.blank_line();
+    /// buffer.println("fn second() {}");
+    ///
+    /// assert_eq!("fn first() {}\n\nfn second() {}", buffer.to_string());
+    /// ```
+    pub fn set_max_consecutive_blanks(&mut self, max: Option<usize>) {
+        self.max_consecutive_blanks = max;
+    }
+
+    /// Set whether [CodeBuffer::println()] panics if it's given a string that
+    /// contains a newline. Such a string is stored as a single line, so only
+    /// its first line is indented and [CodeBuffer::line_count()] is wrong.
+    /// Strict mode catches this mistake early, so multi-line text can be
+    /// written with [CodeBuffer::println_block()] instead. This is off by
+    /// default.
+    ///
+    /// # Arguments
+    ///
+    /// * `strict` - Whether to panic on strings that contain a newline.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use simplegen::CodeBuffer;
+    ///
+    /// let mut buffer = CodeBuffer::default();
+    /// buffer.set_strict_single_line(true);
+    /// buffer.indent_right();
+    ///
+    /// // buffer.println("let x = 1;\nlet y = 2;") would panic.
+    /// buffer.println_block("let x = 1;\nlet y = 2;");
+    ///
+    /// assert_eq!(2, buffer.line_count());
+    /// ```
+    pub fn set_strict_single_line(&mut self, strict: bool) {
+        self.strict_single_line = strict;
+    }
+
+    /// Set text that is written before the contents of the buffer when it is
+    /// output, such as a "generated file" banner. The text is split into lines
+    /// on `\n`, and isn't indented. An empty string removes the preamble.
+    ///
+    /// The preamble is only part of the output, so it isn't included in
+    /// [CodeBuffer::lines()] or [CodeBuffer::len()].
+    ///
+    /// # Arguments
+    ///
+    /// * `preamble` - Text to write before the contents of the buffer.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use simplegen::CodeBuffer;
+    ///
+    /// let mut buffer = CodeBuffer::default();
+    /// buffer.println("fn main() {}");
+    ///
+    /// buffer.set_preamble("// @generated\n// Do not edit.");
+    ///
+    /// assert_eq!(
+    ///     "// @generated\n// Do not edit.\nfn main() {}",
+    ///     buffer.to_string()
+    /// );
+    /// ```
+    pub fn set_preamble(&mut self, preamble: &str) {
+        self.preamble = split_text_lines(preamble);
+    }
+
+    /// Set text that is written after the contents of the buffer when it is
+    /// output. The text is split into lines on `\n`, and isn't indented. An
+    /// empty string removes the epilogue.
+    ///
+    /// The epilogue is only part of the output, so it isn't included in
+    /// [CodeBuffer::lines()] or [CodeBuffer::len()].
+    ///
+    /// # Arguments
+    ///
+    /// * `epilogue` - Text to write after the contents of the buffer.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use simplegen::CodeBuffer;
+    ///
+    /// let mut buffer = CodeBuffer::default();
+    /// buffer.println("#ifndef POINT_H");
+    ///
+    /// buffer.set_epilogue("#endif");
+    ///
+    /// assert_eq!("#ifndef POINT_H\n#endif", buffer.to_string());
+    /// ```
+    pub fn set_epilogue(&mut self, epilogue: &str) {
+        self.epilogue = split_text_lines(epilogue);
+    }
+
+    /// Set the token written before the text of comments by
+    /// [CodeBuffer::println_comment()]. The default is `//`.
+    ///
+    /// # Arguments
+    ///
+    /// * `prefix` - Token that starts a line comment.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use simplegen::CodeBuffer;
+    ///
+    /// let mut buffer = CodeBuffer::default();
+    /// buffer.set_comment_prefix("#");
+    ///
+    /// buffer.println_comment("Hello, World!");
+    ///
+    /// assert_eq!("# Hello, World!", buffer.to_string());
+    /// ```
+    pub fn set_comment_prefix(&mut self, prefix: &str) {
+        self.comment_prefix = prefix.to_string();
+    }
+
+    /// Set the token written at the start of each line inside a block
+    /// comment by [CodeBuffer::wrap_in_block_comment()], such as `" * "`. The
+    /// default is an empty string, so lines are left as they are.
+    ///
+    /// # Arguments
+    ///
+    /// * `prefix` - Token written before each line of a block comment.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use simplegen::CodeBuffer;
+    ///
+    /// let mut buffer = CodeBuffer::default();
+    /// buffer.set_block_comment_line_prefix(" * ");
+    /// buffer.println("Example:");
+    ///
+    /// buffer.wrap_in_block_comment("/*", " */");
+    ///
+    /// assert_eq!("/*\n * Example:\n */", buffer.to_string());
+    /// ```
+    pub fn set_block_comment_line_prefix(&mut self, prefix: &str) {
+        self.block_comment_line_prefix = prefix.to_string();
+    }
+
+    /// Set the templates for the markers written by [CodeBuffer::region()].
+    /// `{name}` in either template is replaced by the name of the region. The
+    /// defaults are `// region: {name}` and `// endregion`.
+    ///
+    /// # Arguments
+    ///
+    /// * `open` - Template for the marker that opens a region.
+    /// * `close` - Template for the marker that closes a region.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use simplegen::CodeBuffer;
+    ///
+    /// let mut buffer = CodeBuffer::default();
+    /// buffer.set_region_markers("#region {name}", "#endregion");
+    ///
+    /// buffer.region("Fields");
+    ///
+    /// assert_eq!("#region Fields\n#endregion", buffer.to_string());
+    /// ```
+    pub fn set_region_markers(&mut self, open: &str, close: &str) {
+        self.region_markers = (open.to_string(), close.to_string());
+    }
+
+    /// Set the tokens written around the body of annotations by
+    /// [CodeBuffer::annotation()]. The defaults are `#[` and `]`, for Rust
+    /// attributes.
+    ///
+    /// # Arguments
+    ///
+    /// * `open` - Token written before the body of an annotation.
+    /// * `close` - Token written after the body of an annotation.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use simplegen::CodeBuffer;
+    ///
+    /// let mut buffer = CodeBuffer::default();
+    /// buffer.set_annotation_style("@", "");
+    ///
+    /// buffer.annotation("Override");
+    ///
+    /// assert_eq!("@Override", buffer.to_string());
+    /// ```
+    pub fn set_annotation_style(&mut self, open: &str, close: &str) {
+        self.annotation_style = (open.to_string(), close.to_string());
+    }
+
+    /// Set the width that lines written by [CodeBuffer::println()] are wrapped
+    /// at. This is `None`, for no wrapping, by default.
+    ///
+    /// A line longer than the width, including its indentation, is split on
+    /// whitespace, and each continuation line is indented one level further
+    /// than the first. Words are never split, so a word that doesn't fit is
+    /// written on a line of its own.
+    ///
+    /// # Arguments
+    ///
+    /// * `max_width` - Width to wrap lines at, or `None` to not wrap lines.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use simplegen::CodeBuffer;
+    ///
+    /// let mut buffer = CodeBuffer::default();
+    /// buffer.set_max_width(Some(24));
+    ///
+    /// buffer.println("SELECT id, name FROM users WHERE active");
+    ///
+    /// assert_eq!(
+    ///     "SELECT id, name FROM\n    users WHERE active",
+    ///     buffer.to_string()
+    /// );
+    /// ```
+    pub fn set_max_width(&mut self, max_width: Option<usize>) {
+        self.max_width = max_width;
+    }
+
+    /// Set the maximum number of columns a line is indented by. Lines at
+    /// deeper levels are indented by as many whole levels as fit in this many
+    /// columns, so very deep recursion in a generator degrades gracefully
+    /// instead of overflowing or allocating huge prefixes. The default is
+    /// 4096 columns.
+    ///
+    /// # Arguments
+    ///
+    /// * `columns` - Maximum number of columns to indent a line by.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use simplegen::CodeBuffer;
+    ///
+    /// let mut buffer = CodeBuffer::new(4);
+    /// buffer.set_max_indent_columns(8);
+    ///
+    /// buffer.indent_right_by(5);
+    /// buffer.println("deep");
+    ///
+    /// assert_eq!("        deep", buffer.to_string());
+    /// ```
+    pub fn set_max_indent_columns(&mut self, columns: usize) {
+        self.max_indent_columns = columns;
+    }
+
+    /// Append a string to the current line without ending it, like `print!`.
+    /// The line is indented at the current level when it is started, and is
+    /// ended by the next call to [CodeBuffer::println()], which appends its
+    /// string to the line first. Any `\n` in the string also ends the line.
+    ///
+    /// # Arguments
+    ///
+    /// * `str` - String to append to the current line.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use simplegen::CodeBuffer;
+    ///
+    /// let mut buffer = CodeBuffer::default();
+    /// buffer.indent_right();
+    ///
+    /// for token in &["let", " ", "x"] {
+    ///     buffer.print(token);
+    /// }
+    /// buffer.println(" = 1;");
+    ///
+    /// assert_eq!("    let x = 1;", buffer.to_string());
+    /// ```
+    pub fn print(&mut self, str: &str) -> &mut Self {
+        // Writing to a CodeBuffer never fails.
+        fmt::Write::write_str(self, str).unwrap();
+        self
+    }
+
+    /// Write a line to the internal buffer at the current indentation level.
+    /// If a line has been started by [CodeBuffer::print()] or `write!`, the
+    /// string is appended to it and the line is ended instead. Continued lines
+    /// aren't wrapped by [CodeBuffer::set_max_width()].
+    ///
+    /// # Arguments
+    ///
+    /// * `str` - String to append to the buffer.
+    ///
+    /// # Panics
+    ///
+    /// Panics if [CodeBuffer::set_strict_single_line()] is on and `str`
+    /// contains a newline.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use simplegen::CodeBuffer;
+    ///
+    /// let mut buffer = CodeBuffer::default();
+    ///
+    /// // Calling this will append "Hello, World!" to the buffer at the current
+    /// // indentation level, in this case an indentation level of 0.
+    /// buffer.println("Hello, World!");
+    /// ```
+    pub fn println(&mut self, str: &str) -> &mut Self {
+        self.assert_single_line(str);
+
+        if let Some(mut line) = self.pending.take() {
+            line.push_str(str);
+            self.push_line(line);
+            return self;
+        }
+
+        let line = self.indent_line(str);
+
+        match self.max_width {
+            Some(max_width) if text_width(&line) > max_width && !str.trim().is_empty() => {
+                let first_width = max_width.saturating_sub(self.prefix_width(self.level));
+                let width =
+                    max_width.saturating_sub(self.prefix_width(self.level.saturating_add(1)));
+
+                let wrapped = wrap_words_hanging(str, first_width, width);
+                for (index, line) in wrapped.iter().enumerate() {
+                    let level = if index == 0 {
+                        self.level
+                    } else {
+                        self.level.saturating_add(1)
+                    };
+                    let line = self.indent_line_at(level, line);
+                    self.buffer.push(line);
+                }
+            }
+            _ => self.push_line(line),
+        }
+        self
+    }
+
+    /// Write a line to the internal buffer at the current indentation level,
+    /// unless the same string has already been written by an earlier call to
+    /// `println_once`. Lines written by other methods aren't checked. This is
+    /// useful for imports that several parts of a generator may need.
+    ///
+    /// # Arguments
+    ///
+    /// * `str` - String to append to the buffer.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use simplegen::CodeBuffer;
+    ///
+    /// let mut buffer = CodeBuffer::default();
+    /// buffer.println_once("use std::fmt;");
+    /// buffer.println_once("use std::io;");
+    /// buffer.println_once("use std::fmt;");
+    ///
+    /// assert_eq!("use std::fmt;\nuse std::io;", buffer.to_string());
+    /// ```
+    pub fn println_once(&mut self, str: &str) -> &mut Self {
+        if !self.written_once.contains_key(str) {
+            let order = self.written_once.len();
+            self.written_once.insert(str.to_string(), order);
+            self.println(str);
+        }
+        self
+    }
+
+    /// Write a line like [CodeBuffer::println()] and return its position in
+    /// the buffer. If the line is wrapped by [CodeBuffer::set_max_width()],
+    /// the position of its first line is returned. The position can be passed
+    /// to [CodeBuffer::replace_line()] or [CodeBuffer::insert_line()] later,
+    /// which is useful for back-patching labels or jump targets.
+    ///
+    /// # Arguments
+    ///
+    /// * `str` - String to append to the buffer.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use simplegen::CodeBuffer;
+    ///
+    /// let mut buffer = CodeBuffer::default();
+    /// buffer.println("start:");
+    /// let jump = buffer.println_indexed("jmp ???");
+    /// buffer.println("end:");
+    ///
+    /// buffer.replace_line(jump, "jmp end");
+    ///
+    /// assert_eq!("start:\njmp end\nend:", buffer.to_string());
+    /// ```
+    pub fn println_indexed(&mut self, str: &str) -> usize {
+        let position = self.buffer.len();
+        self.println(str);
+        position
+    }
+
+    /// Write an empty line to the internal buffer. Like `println("")`, the
+    /// line isn't indented, so it never contains trailing whitespace, even if
+    /// [CodeBuffer::set_indent_blank_lines()] is on.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use simplegen::CodeBuffer;
+    ///
+    /// let mut buffer = CodeBuffer::default();
+    ///
+    /// buffer.println("fn first() {}");
+    /// buffer.blank_line();
+    /// buffer.println("fn second() {}");
+    ///
+    /// assert_eq!("fn first() {}\n\nfn second() {}", buffer.to_string());
+    /// ```
+    pub fn blank_line(&mut self) {
+        self.println_raw("");
+    }
+
+    /// Write a block of text to the internal buffer, splitting it on `\n` and
+    /// writing each line at the current indentation level. Empty lines in the
+    /// block are written without indentation, so they stay truly empty.
+    ///
+    /// # Arguments
+    ///
+    /// * `str` - Block of text to append to the buffer.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use simplegen::CodeBuffer;
+    ///
+    /// let mut buffer = CodeBuffer::default();
+    ///
+    /// buffer.indent_right();
+    /// buffer.println_block("let x = 1;\n\nlet y = 2;");
+    ///
+    /// assert_eq!("    let x = 1;\n\n    let y = 2;", buffer.to_string());
+    /// ```
+    pub fn println_block(&mut self, str: &str) {
+        for line in str.split('\n') {
+            if line.is_empty() {
+                self.blank_line();
+            } else {
+                self.println(line);
+            }
+        }
+    }
+
+    /// Write tab-indented text to the internal buffer at the current
+    /// indentation level. Each tab at the start of a line becomes one level of
+    /// indentation in the buffer's [IndentStyle], so tab-indented snippets
+    /// match the rest of the buffer. If `all_tabs` is false, tabs after the
+    /// first character that isn't a tab are left alone, otherwise they're
+    /// replaced by spaces up to the next tab stop, with tab stops every
+    /// [CodeBuffer::indent_width()] columns. Lines are split on `\n` and
+    /// written like [CodeBuffer::println_block()].
+    ///
+    /// # Arguments
+    ///
+    /// * `str` - Text to write.
+    /// * `all_tabs` - Whether to expand tabs in the middle of lines as well.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use simplegen::CodeBuffer;
+    ///
+    /// let mut buffer = CodeBuffer::new(2);
+    /// buffer.println_expand_tabs("if x {\n\ty();\t// call\n}", false);
+    /// buffer.println_expand_tabs("z();\t// call", true);
+    ///
+    /// assert_eq!(
+    ///     "if x {\n  y();\t// call\n}\nz();  // call",
+    ///     buffer.to_string()
+    /// );
+    /// ```
+    pub fn println_expand_tabs(&mut self, str: &str, all_tabs: bool) {
+        let width = self.indent_width() as usize;
+        for line in str.split('\n') {
+            let content = line.trim_start_matches('\t');
+            let tabs = (line.len() - content.len()) as u32;
+
+            let mut expanded = String::with_capacity(content.len());
+            let mut column = 0;
+            for c in content.chars() {
+                if c == '\t' && all_tabs {
+                    let spaces = if width == 0 {
+                        0
+                    } else {
+                        width - column % width
+                    };
+                    expanded.extend(core::iter::repeat(' ').take(spaces));
+                    column += spaces;
+                } else {
+                    expanded.push(c);
+                    column += 1;
+                }
+            }
+
+            let level = self.level + tabs;
+            self.at_level(level, |buffer| {
+                buffer.println(&expanded);
+            });
+        }
+    }
+
+    /// Splice a block of pre-rendered text, such as the output of an external
+    /// formatter, into the buffer at the current indentation level. This is
+    /// the same as [CodeBuffer::println_block()]: each line is indented, and
+    /// blank lines stay truly empty. Use [CodeBuffer::println_raw()] instead to
+    /// keep the text's lines exactly as they are.
+    ///
+    /// # Arguments
+    ///
+    /// * `block` - Text to write, with lines separated by `\n`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use simplegen::CodeBuffer;
+    ///
+    /// let mut buffer = CodeBuffer::default();
+    /// buffer.println("mod generated {");
+    /// buffer.indent_right();
+    /// buffer.println_indented_block("fn a() {}\n\nfn b() {}");
+    /// buffer.println_left("}");
+    ///
+    /// assert_eq!(
+    ///     "mod generated {\n    fn a() {}\n\n    fn b() {}\n}",
+    ///     buffer.to_string()
+    /// );
+    /// ```
+    pub fn println_indented_block(&mut self, block: &str) {
+        self.println_block(block);
+    }
+
+    #[cfg(feature = "std")]
+    /// Write a template to the internal buffer at the current indentation
+    /// level, replacing each `{key}` placeholder with its value in `vars`.
+    /// Placeholders without a value are left in the output as they are, so
+    /// they're easy to spot. Each line of the filled in template is written
+    /// like [CodeBuffer::println_block()].
+    ///
+    /// # Arguments
+    ///
+    /// * `template` - Text containing `{key}` placeholders.
+    /// * `vars` - Value to replace each placeholder with, by key.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use simplegen::CodeBuffer;
+    /// use std::collections::HashMap;
+    ///
+    /// let mut vars = HashMap::new();
+    /// vars.insert("name", "x");
+    /// vars.insert("value", "1");
+    ///
+    /// let mut buffer = CodeBuffer::default();
+    /// buffer.println_template("let {name} = {value};", &vars);
+    ///
+    /// assert_eq!("let x = 1;", buffer.to_string());
+    /// ```
+    pub fn println_template(&mut self, template: &str, vars: &HashMap<&str, &str>) {
+        let filled = fill_placeholders(template, |key| vars.get(key).copied());
+        self.println_block(&filled);
+    }
+
+    /// Render a template against a [TemplateContext] and write each line at
+    /// the current indentation level, like [CodeBuffer::println_block()].
+    ///
+    /// Besides `{key}` placeholders, templates support two kinds of block:
+    ///
+    /// * `{{#if key}}...{{/if}}` is kept only if `key` is set to a value or
+    ///   list that isn't empty.
+    /// * `{{#each key}}...{{/each}}` is repeated for each item in the list
+    ///   `key`, with `{this}` replaced by the item.
+    ///
+    /// A block tag on a line of its own is removed along with its line, so
+    /// tags don't leave blank lines behind. A newline at the end of the
+    /// rendered template doesn't add an empty line, and a template that
+    /// renders to nothing writes nothing.
+    ///
+    /// # Arguments
+    ///
+    /// * `template` - Text containing placeholders and blocks.
+    /// * `ctx` - Values and lists to fill the template in with.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a block is never closed, or is closed by the wrong tag.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use simplegen::{CodeBuffer, TemplateContext};
+    ///
+    /// let mut ctx = TemplateContext::new();
+    /// ctx.set("name", "Color").set("derive", "Debug");
+    /// ctx.set_list("variants", ["Red", "Green"]);
+    ///
+    /// let template = r#"{{#if derive}}
+    /// #[derive({derive})]
+    /// {{/if}}
+    /// enum {name} {
+    ///     {{#each variants}}
+    ///     {this},
+    ///     {{/each}}
+    /// }
+    /// "#;
+    ///
+    /// let mut buffer = CodeBuffer::default();
+    /// buffer.render_template(template, &ctx);
+    ///
+    /// assert_eq!(
+    ///     "#[derive(Debug)]\nenum Color {\n    Red,\n    Green,\n}",
+    ///     buffer.to_string()
+    /// );
+    /// ```
+    pub fn render_template(&mut self, template: &str, ctx: &TemplateContext) {
+        let rendered = render_template(template, ctx);
+        let rendered = rendered.strip_suffix('\n').unwrap_or(&rendered);
+        if !rendered.is_empty() {
+            self.println_block(rendered);
+        }
+    }
+
+    /// Write a comment to the internal buffer at the current indentation
+    /// level. Each line of the text is written as its own comment, prefixed by
+    /// the comment prefix and a space.
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - Text of the comment.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use simplegen::CodeBuffer;
+    ///
+    /// let mut buffer = CodeBuffer::default();
+    ///
+    /// buffer.println_comment("Adds one to x.\nNever overflows.");
+    /// buffer.println("fn add_one(x: u8) -> u8 { x.saturating_add(1) }");
+    ///
+    /// assert_eq!(
+    ///     "// Adds one to x.\n// Never overflows.\nfn add_one(x: u8) -> u8 { x.saturating_add(1) }",
+    ///     buffer.to_string()
+    /// );
+    /// ```
+    pub fn println_comment(&mut self, text: &str) {
+        for line in text.split('\n') {
+            let comment = if line.is_empty() {
+                self.comment_prefix.clone()
+            } else {
+                format!("{} {}", self.comment_prefix, line)
+            };
+            self.println(&comment);
+        }
+    }
+
+    /// Append an end of line comment, made of the comment prefix and `comment`,
+    /// to the last line written to the buffer. If a line has been started by
+    /// [CodeBuffer::print()] or `write!` and not ended yet, the comment is
+    /// appended to that line.
+    ///
+    /// Returns an error if the buffer has no lines.
+    ///
+    /// # Arguments
+    ///
+    /// * `comment` - Text of the comment.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use simplegen::CodeBuffer;
+    ///
+    /// let mut buffer = CodeBuffer::default();
+    /// buffer.println("let x = 42;");
+    /// buffer.append_eol_comment("the answer").unwrap();
+    ///
+    /// assert_eq!("let x = 42; // the answer", buffer.to_string());
+    /// ```
+    pub fn append_eol_comment(&mut self, comment: &str) -> Result<(), EmptyBufferError> {
+        let suffix = format!(" {} {}", self.comment_prefix, comment);
+        let line = match self.pending.as_mut() {
+            Some(pending) => pending,
+            None => self.buffer.last_mut().ok_or(EmptyBufferError)?,
+        };
+        line.push_str(&suffix);
+        Ok(())
+    }
+
+    /// Write a banner comment to the internal buffer at the current
+    /// indentation level, made of a line of `fill` characters, the title
+    /// centered on its own line, and another line of `fill` characters. Each
+    /// line starts with the comment prefix, and is `width` characters wide,
+    /// not counting indentation. A title too long to fit is truncated.
+    ///
+    /// # Arguments
+    ///
+    /// * `title` - Title to center in the banner.
+    /// * `width` - Width of the banner, including the comment prefix.
+    /// * `fill` - Character that the lines above and below the title are made
+    ///   of.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use simplegen::CodeBuffer;
+    ///
+    /// let mut buffer = CodeBuffer::default();
+    /// buffer.banner("Models", 12, '=');
+    ///
+    /// assert_eq!(
+    ///     "//==========\n//  Models\n//==========",
+    ///     buffer.to_string()
+    /// );
+    /// ```
+    pub fn banner(&mut self, title: &str, width: usize, fill: char) {
+        let inner_width = width.saturating_sub(text_width(&self.comment_prefix));
+        let title: String = title.chars().take(inner_width).collect();
+        let padding = (inner_width - text_width(&title)) / 2;
+
+        let fill_line = format!(
+            "{}{}",
+            self.comment_prefix,
+            fill.to_string().repeat(inner_width)
+        );
+        let title_line = format!("{}{}{}", self.comment_prefix, " ".repeat(padding), title);
+
+        self.println(&fill_line);
+        self.println(&title_line);
+        self.println(&fill_line);
+    }
+
+    /// Write an annotation, such as a Rust attribute, Java annotation or
+    /// Python decorator, to the internal buffer at the current indentation
+    /// level. The body is wrapped in the tokens set by
+    /// [CodeBuffer::set_annotation_style()], which are `#[` and `]` by default.
+    ///
+    /// # Arguments
+    ///
+    /// * `body` - Body of the annotation.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use simplegen::CodeBuffer;
+    ///
+    /// let mut buffer = CodeBuffer::default();
+    /// buffer.annotation("derive(Debug)");
+    /// buffer.println("struct Unit;");
+    ///
+    /// assert_eq!("#[derive(Debug)]\nstruct Unit;", buffer.to_string());
+    /// ```
+    pub fn annotation(&mut self, body: &str) -> &mut Self {
+        let (open, close) = &self.annotation_style;
+        let line = format!("{}{}{}", open, body, close);
+        self.println(&line)
+    }
+
+    /// Write a `key{sep}value` line, such as a line of a TOML, INI or `.env`
+    /// file, to the internal buffer at the current indentation level.
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - Key of the pair.
+    /// * `value` - Value of the pair, written as it is.
+    /// * `sep` - Separator between the key and value, such as `" = "`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use simplegen::CodeBuffer;
+    ///
+    /// let mut buffer = CodeBuffer::default();
+    /// buffer.key_value("PORT", "8080", "=");
+    ///
+    /// assert_eq!("PORT=8080", buffer.to_string());
+    /// ```
+    pub fn key_value(&mut self, key: &str, value: &str, sep: &str) -> &mut Self {
+        let line = format!("{}{}{}", key, sep, value);
+        self.println(&line)
+    }
+
+    /// Write a `key{sep}"value"` line to the internal buffer at the current
+    /// indentation level, with the value wrapped in double quotes. Double
+    /// quotes and backslashes in the value are escaped with a backslash. For
+    /// other escaping, escape the value with a function from
+    /// [escape](crate::escape) and use [CodeBuffer::key_value()].
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - Key of the pair.
+    /// * `value` - Value of the pair, to be quoted.
+    /// * `sep` - Separator between the key and value, such as `" = "`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use simplegen::CodeBuffer;
+    ///
+    /// let mut buffer = CodeBuffer::default();
+    /// buffer.println("[package]");
+    /// buffer.key_value_quoted("name", "simplegen", " = ");
+    ///
+    /// assert_eq!("[package]\nname = \"simplegen\"", buffer.to_string());
+    /// ```
+    pub fn key_value_quoted(&mut self, key: &str, value: &str, sep: &str) -> &mut Self {
+        let quoted = value.replace('\\', "\\\\").replace('"', "\\\"");
+        let line = format!("{}{}\"{}\"", key, sep, quoted);
+        self.println(&line)
+    }
+
+    /// Write a Rust doc comment to the internal buffer at the current
+    /// indentation level. Each line of the text becomes a `///` line, and an
+    /// empty text writes a single `///` line.
+    ///
+    /// If `wrap_at` is given, lines are word-wrapped so that no line,
+    /// including its indentation and the `/// ` prefix, is longer than that
+    /// many characters. Words are never split, so a word that doesn't fit is
+    /// written on a line of its own.
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - Text of the doc comment.
+    /// * `wrap_at` - Column to wrap lines at, if any.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use simplegen::CodeBuffer;
+    ///
+    /// let mut buffer = CodeBuffer::default();
+    ///
+    /// buffer.doc_comment("A point in two dimensional space.", Some(20));
+    /// buffer.println("struct Point(i32, i32);");
+    ///
+    /// assert_eq!(
+    ///     "/// A point in two\n/// dimensional\n/// space.\nstruct Point(i32, i32);",
+    ///     buffer.to_string()
+    /// );
+    /// ```
+    pub fn doc_comment(&mut self, text: &str, wrap_at: Option<usize>) {
+        let indent_width = self.prefix_width(self.level);
+
+        for line in text.split('\n') {
+            let wrapped = match wrap_at {
+                Some(column) => wrap_words(line, column.saturating_sub(indent_width + 4)),
+                None => vec![line.to_string()],
+            };
+
+            if wrapped.iter().all(|line| line.is_empty()) {
+                self.println("///");
+            }
+            for line in wrapped.iter().filter(|line| !line.is_empty()) {
+                self.println(&format!("/// {}", line));
+            }
+        }
+    }
+
+    /// Write the rows of a [Table] to the internal buffer at the current
+    /// indentation level, with the columns of the table aligned.
+    ///
+    /// # Arguments
+    ///
+    /// * `table` - Table to append to the buffer.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use simplegen::{CodeBuffer, Table};
+    ///
+    /// let mut table = Table::new();
+    /// table.row(&["Red", "= 1,"]);
+    /// table.row(&["Green", "= 2,"]);
+    ///
+    /// let mut buffer = CodeBuffer::default();
+    /// buffer.println("enum Colour {");
+    /// buffer.indent_right();
+    /// buffer.println_table(&table);
+    /// buffer.println_left("}");
+    ///
+    /// assert_eq!(
+    ///     "enum Colour {\n    Red   = 1,\n    Green = 2,\n}",
+    ///     buffer.to_string()
+    /// );
+    /// ```
+    pub fn println_table(&mut self, table: &Table) {
+        for line in table.render() {
+            self.println(&line);
+        }
+    }
+
+    /// Write a fixed-width record to the internal buffer at the current
+    /// indentation level. Each field is padded with spaces to its width, and
+    /// a field longer than its width is truncated, so every field starts at
+    /// the same column in every record.
+    ///
+    /// # Arguments
+    ///
+    /// * `fields` - Text and width, in characters, of each field.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use simplegen::CodeBuffer;
+    ///
+    /// let mut buffer = CodeBuffer::default();
+    /// buffer.println_fields(&[("ID", 4), ("NAME", 6)]);
+    /// buffer.println_fields(&[("1", 4), ("Ferris the crab", 6)]);
+    ///
+    /// assert_eq!("ID  NAME  \n1   Ferris", buffer.to_string());
+    /// ```
+    pub fn println_fields(&mut self, fields: &[(&str, usize)]) -> &mut Self {
+        let mut record = String::new();
+        for (text, width) in fields {
+            let truncated: String = text.chars().take(*width).collect();
+            record.push_str(&pad_right(&truncated, *width, ' '));
+        }
+        self.println(&record)
+    }
+
+    /// Write a string literal to the internal buffer at the current
+    /// indentation level, escaped with the given function. The functions in
+    /// the [escape](crate::escape) module can be used for common languages.
+    ///
+    /// # Arguments
+    ///
+    /// * `value` - String to write as a literal.
+    /// * `escape` - Function that turns the string into an escaped literal.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use simplegen::escape::escape_json_str;
+    /// use simplegen::CodeBuffer;
+    ///
+    /// let mut buffer = CodeBuffer::default();
+    /// buffer.println("[");
+    /// buffer.indent_right();
+    /// buffer.println_str_literal("say \"hi\"", escape_json_str);
+    /// buffer.println_left("]");
+    ///
+    /// assert_eq!("[\n    \"say \\\"hi\\\"\"\n]", buffer.to_string());
+    /// ```
+    pub fn println_str_literal<F>(&mut self, value: &str, escape: F) -> &mut Self
+    where
+        F: FnOnce(&str) -> String,
+    {
+        self.println(&escape(value))
+    }
+
+    /// Write a line at the current indentation level, followed by
+    /// continuation lines that are aligned to a column, such as the arguments
+    /// of a wrapped function signature. Each continuation line is indented to
+    /// the current indentation level, then padded with `align_col` spaces.
+    /// Continuation lines aren't wrapped by [CodeBuffer::set_max_width()], so
+    /// they keep their alignment.
+    ///
+    /// # Arguments
+    ///
+    /// * `first` - First line, written at the current indentation level.
+    /// * `continuations` - Lines to align under the first line.
+    /// * `align_col` - Column to align continuation lines to, relative to the
+    ///   current indentation.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use simplegen::CodeBuffer;
+    ///
+    /// let mut buffer = CodeBuffer::default();
+    ///
+    /// buffer.println_continued("fn add(x: u64,", &["y: u64,", "z: u64) -> u64;"], 7);
+    ///
+    /// assert_eq!(
+    ///     "fn add(x: u64,\n       y: u64,\n       z: u64) -> u64;",
+    ///     buffer.to_string()
+    /// );
+    /// ```
+    pub fn println_continued(&mut self, first: &str, continuations: &[&str], align_col: usize) {
+        self.println(first);
+
+        let padding = " ".repeat(align_col);
+        for continuation in continuations {
+            let line = self.indent_line(&format!("{}{}", padding, continuation));
+            self.push_line(line);
+        }
+    }
+
+    /// Write a line to the internal buffer verbatim, without any indentation.
+    /// The indentation level is left unchanged for the lines that follow.
+    ///
+    /// # Arguments
+    ///
+    /// * `str` - String to append to the buffer.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use simplegen::CodeBuffer;
+    ///
+    /// let mut buffer = CodeBuffer::default();
+    ///
+    /// buffer.println("int main() {");
+    /// buffer.indent_right();
+    /// // Preprocessor directives must start at column zero.
+    /// buffer.println_raw("#ifdef DEBUG");
+    /// buffer.println("puts(\"debug\");");
+    /// buffer.println_raw("#endif");
+    /// buffer.println_left("}");
+    ///
+    /// assert_eq!(
+    ///     "int main() {\n#ifdef DEBUG\n    puts(\"debug\");\n#endif\n}",
+    ///     buffer.to_string()
+    /// );
+    /// ```
+    pub fn println_raw(&mut self, str: &str) {
+        self.commit_pending();
+        self.push_raw_line(str.to_string());
+    }
+
+    /// Write formatted arguments as a line to the internal buffer at the
+    /// current indentation level. This is usually called through the
+    /// [println_fmt!](crate::println_fmt) macro.
+    ///
+    /// # Arguments
+    ///
+    /// * `args` - Formatted arguments, as created by `format_args!`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if [CodeBuffer::set_strict_single_line()] is on and the
+    /// formatted line contains a newline.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use simplegen::CodeBuffer;
+    ///
+    /// let mut buffer = CodeBuffer::default();
+    ///
+    /// buffer
+    ///     .println_fmt(format_args!("let {} = {};", "x", 1))
+    ///     .println("let y = x;");
+    ///
+    /// assert_eq!("let x = 1;\nlet y = x;", buffer.to_string());
+    /// ```
+    pub fn println_fmt(&mut self, args: fmt::Arguments) -> &mut Self {
+        if self.max_width.is_some() {
+            return self.println(&args.to_string());
+        }
+
+        let mut line = match self.pending.take() {
+            Some(line) => line,
+            None => self.indent_line(""),
+        };
+        let start = line.len();
+        // Writing to a String never fails.
+        fmt::Write::write_fmt(&mut line, args).unwrap();
+        self.assert_single_line(&line[start..]);
+        self.push_line(line);
+        self
+    }
+
+    /// Indent the internal buffer right.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use simplegen::CodeBuffer;
+    /// let mut buffer = CodeBuffer::default();
+    ///
+    /// // Increases indentation level by one.
+    /// buffer.indent_right();
+    ///
+    /// buffer.println("Hello, World!");
+    /// // The string "    Hello, World!" gets appended to the buffer, as we
+    /// // have increased indentation level by one, and default indentation
+    /// // level is 4 spaces.
+    /// ```
+    pub fn indent_right(&mut self) -> &mut Self {
+        self.level = self.level.saturating_add(1);
+        self
+    }
+
+    /// Indent the internal buffer left.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use simplegen::CodeBuffer;
+    /// let mut buffer = CodeBuffer::default();
+    ///
+    /// // We set the indentation level to 1 for demonstration purposes.
+    /// buffer.indent_right();
+    ///
+    /// buffer.indent_left();
+    /// // The indentation level has returned to 0.
+    ///
+    /// buffer.println("Hello, World!");
+    /// // The string "Hello, World!" is appended to the buffer, as we set the
+    /// // indentation level from 1, to 0.
+    /// ```
+    pub fn indent_left(&mut self) -> &mut Self {
+        if self.level > 0 {
+            self.level -= 1;
+        }
+        self
+    }
+
+    /// Indent the internal buffer right by several levels at once.
+    ///
+    /// # Arguments
+    ///
+    /// * `n` - Number of levels to indent by.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use simplegen::CodeBuffer;
+    /// let mut buffer = CodeBuffer::default();
+    ///
+    /// // Increases indentation level by three.
+    /// buffer.indent_right_by(3);
+    ///
+    /// assert_eq!(3, buffer.level());
+    /// ```
+    pub fn indent_right_by(&mut self, n: u32) {
+        self.level = self.level.saturating_add(n);
+    }
+
+    /// Indent the internal buffer left by several levels at once. The
+    /// indentation level never goes below 0.
+    ///
+    /// # Arguments
+    ///
+    /// * `n` - Number of levels to indent by.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use simplegen::CodeBuffer;
+    /// let mut buffer = CodeBuffer::default();
+    ///
+    /// buffer.indent_right_by(3);
+    /// buffer.indent_left_by(2);
+    ///
+    /// assert_eq!(1, buffer.level());
+    /// ```
+    pub fn indent_left_by(&mut self, n: u32) {
+        self.level = self.level.saturating_sub(n);
+    }
+
+    /// Set the indentation level of the internal buffer directly.
+    ///
+    /// # Arguments
+    ///
+    /// * `level` - Indentation level to use for the following lines.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use simplegen::CodeBuffer;
+    /// let mut buffer = CodeBuffer::default();
+    ///
+    /// buffer.set_level(2);
+    ///
+    /// buffer.println("Hello, World!");
+    /// // The string "        Hello, World!" is appended to the buffer.
+    /// ```
+    pub fn set_level(&mut self, level: u32) {
+        self.level = level;
+    }
+
+    /// Set a base indentation level that every indented line is shifted right
+    /// by, on top of the current indentation level. This is 0 by default, and
+    /// doesn't change the value returned by [CodeBuffer::level()]. Lines
+    /// written without indentation, such as by [CodeBuffer::println_raw()],
+    /// aren't shifted.
+    ///
+    /// # Arguments
+    ///
+    /// * `base_level` - Number of levels to shift indented lines right by.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use simplegen::CodeBuffer;
+    /// let mut buffer = CodeBuffer::default();
+    ///
+    /// // The generated code is embedded inside an existing block.
+    /// buffer.set_base_level(1);
+    ///
+    /// buffer.println("fn method() {}");
+    ///
+    /// assert_eq!("    fn method() {}", buffer.to_string());
+    /// assert_eq!(0, buffer.level());
+    /// ```
+    pub fn set_base_level(&mut self, base_level: u32) {
+        self.base_level = base_level;
+    }
+
+    /// Check that the indentation level has returned to 0, which it should
+    /// have at the end of generation if every indent right was matched by an
+    /// indent left.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use simplegen::CodeBuffer;
+    /// let mut buffer = CodeBuffer::default();
+    ///
+    /// buffer.println("fn main() {");
+    /// buffer.println_right("println!(\"Hello, World!\");");
+    /// // The closing brace was forgotten.
+    ///
+    /// let error = buffer.assert_balanced().unwrap_err();
+    /// assert_eq!(1, error.level);
+    /// ```
+    pub fn assert_balanced(&self) -> Result<(), UnbalancedIndentError> {
+        if self.level == 0 {
+            Ok(())
+        } else {
+            Err(UnbalancedIndentError { level: self.level })
+        }
+    }
+
+    /// Reset the indentation level of the internal buffer to 0.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use simplegen::CodeBuffer;
+    /// let mut buffer = CodeBuffer::default();
+    ///
+    /// buffer.set_level(3);
+    /// buffer.reset_indent();
+    ///
+    /// assert_eq!(0, buffer.level());
+    /// ```
+    pub fn reset_indent(&mut self) {
+        self.set_level(0);
+    }
+
+    /// Save the current indentation level on a stack, so it can be restored
+    /// with [CodeBuffer::pop_level()]. This lets a helper indent however it
+    /// needs to while leaving its caller's level untouched.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use simplegen::CodeBuffer;
+    ///
+    /// let mut buffer = CodeBuffer::default();
+    /// buffer.push_level();
+    /// buffer.indent_right_by(3);
+    /// buffer.pop_level();
+    ///
+    /// assert_eq!(0, buffer.level());
+    /// ```
+    pub fn push_level(&mut self) {
+        self.level_stack.push(self.level);
+    }
+
+    /// Restore the indentation level most recently saved by
+    /// [CodeBuffer::push_level()]. If no level has been saved, this does
+    /// nothing, matching [CodeBuffer::indent_left()] at level 0.
+    pub fn pop_level(&mut self) {
+        if let Some(level) = self.level_stack.pop() {
+            self.set_level(level);
+        }
+    }
+
+    /// Run a closure with the indentation level temporarily set to `level`.
+    /// The previous indentation level is restored when the closure returns.
+    ///
+    /// # Arguments
+    ///
+    /// * `level` - Indentation level to use inside the closure.
+    /// * `f` - Closure that writes to the buffer.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use simplegen::CodeBuffer;
+    /// let mut buffer = CodeBuffer::default();
+    ///
+    /// buffer.at_level(1, |buffer| {
+    ///     buffer.println("Hello, World!");
+    /// });
+    ///
+    /// assert_eq!("    Hello, World!", buffer.to_string());
+    /// assert_eq!(0, buffer.level());
+    /// ```
+    pub fn at_level<F: FnOnce(&mut Self)>(&mut self, level: u32, f: F) {
+        let previous = self.level;
+        self.level = level;
+        f(self);
+        self.level = previous;
+    }
+
+    /// Run a closure with the indentation level temporarily set to 0. The
+    /// previous indentation level is restored when the closure returns.
+    ///
+    /// # Arguments
+    ///
+    /// * `f` - Closure that writes to the buffer.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use simplegen::CodeBuffer;
+    /// let mut buffer = CodeBuffer::default();
+    ///
+    /// buffer.println("mod inner {");
+    /// buffer.indent_right();
+    /// // Hoist an import up to the top level.
+    /// buffer.at_root(|buffer| {
+    ///     buffer.println("use std::fmt;");
+    /// });
+    /// buffer.println_left("}");
+    ///
+    /// assert_eq!("mod inner {\nuse std::fmt;\n}", buffer.to_string());
+    /// ```
+    pub fn at_root<F: FnOnce(&mut Self)>(&mut self, f: F) {
+        self.at_level(0, f);
+    }
+
+    /// Run a closure with indentation turned off, so every line it writes
+    /// starts at the first column. Unlike [CodeBuffer::at_root()], this also
+    /// ignores the base level. This is useful for raw multi-line literals,
+    /// such as embedded SQL, inside indented code.
+    ///
+    /// # Arguments
+    ///
+    /// * `f` - Function that writes the unindented lines.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use simplegen::CodeBuffer;
+    ///
+    /// let mut buffer = CodeBuffer::default();
+    /// buffer.set_base_level(1);
+    /// buffer.println("const QUERY: &str = r#\"");
+    /// buffer.with_no_indent(|buffer| {
+    ///     buffer.println("SELECT *");
+    ///     buffer.println("FROM users");
+    /// });
+    /// buffer.println("\"#;");
+    ///
+    /// assert_eq!(
+    ///     "    const QUERY: &str = r#\"\nSELECT *\nFROM users\n    \"#;",
+    ///     buffer.to_string()
+    /// );
+    /// ```
+    pub fn with_no_indent<F: FnOnce(&mut Self)>(&mut self, f: F) {
+        let previous = self.no_indent;
+        self.no_indent = true;
+        f(self);
+        self.no_indent = previous;
+    }
+
+    /// Run a closure one indentation level to the right, and return whether
+    /// it wrote anything. The previous indentation level is restored when the
+    /// closure returns, so if nothing was written the buffer is left exactly
+    /// as it was. This is useful for leaving out the surrounding code of a
+    /// block that turned out to be empty.
+    ///
+    /// # Arguments
+    ///
+    /// * `f` - Closure that writes the indented lines.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use simplegen::CodeBuffer;
+    ///
+    /// let fields: Vec<&str> = Vec::new();
+    /// let mut buffer = CodeBuffer::default();
+    ///
+    /// let body = buffer.capture(|buffer| {
+    ///     let written = buffer.with_indented_nonempty(|buffer| {
+    ///         for field in &fields {
+    ///             buffer.println(field);
+    ///         }
+    ///     });
+    ///     assert!(!written);
+    /// });
+    ///
+    /// assert_eq!("", body);
+    /// assert_eq!(0, buffer.level());
+    /// ```
+    pub fn with_indented_nonempty<F: FnOnce(&mut Self)>(&mut self, f: F) -> bool {
+        let line_count = self.buffer.len();
+        let pending = self.pending.clone();
+        let previous = self.level;
+        self.level = previous.saturating_add(1);
+        f(self);
+        self.level = previous;
+        self.buffer.len() != line_count || self.pending != pending
+    }
+
+    /// Run `f` against a new, empty `CodeBuffer` and return what it wrote,
+    /// without writing anything to this buffer. The new buffer starts at the
+    /// current indentation level and shares this buffer's settings, apart from
+    /// the preamble and epilogue, so the captured code can be measured or
+    /// inspected before deciding whether to write it.
+    ///
+    /// # Arguments
+    ///
+    /// * `f` - Function that writes the code to capture.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use simplegen::CodeBuffer;
+    ///
+    /// let mut buffer = CodeBuffer::default();
+    /// buffer.println("fn main() {");
+    /// buffer.indent_right();
+    ///
+    /// let body = buffer.capture(|buffer| {
+    ///     buffer.println("run();");
+    /// });
+    ///
+    /// assert_eq!("    run();", body);
+    /// assert_eq!("fn main() {", buffer.to_string());
+    /// ```
+    pub fn capture<F: FnOnce(&mut CodeBuffer)>(&mut self, f: F) -> String {
+        let mut sub_buffer = self.sub_buffer();
+        f(&mut sub_buffer);
+        sub_buffer.to_string()
+    }
+
+    /// Create a new, empty `CodeBuffer` at indentation level 0, with the same
+    /// settings as this buffer, such as the indentation style, line ending and
+    /// trailing newline. The preamble and epilogue aren't copied. This is
+    /// useful for generating several sections separately in the same style.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use simplegen::{CodeBuffer, LineEnding};
+    ///
+    /// let mut buffer = CodeBuffer::new(2);
+    /// buffer.set_line_ending(LineEnding::Crlf);
+    /// buffer.println("fn main() {}");
+    ///
+    /// let mut section = buffer.fork();
+    /// section.println("a {");
+    /// section.println_right("b");
+    ///
+    /// assert_eq!("a {\r\n  b", section.to_string());
+    /// ```
+    pub fn fork(&self) -> CodeBuffer {
+        let mut forked = self.sub_buffer();
+        forked.level = 0;
+        forked
+    }
+
+    /// Indent right then print a string to the internal buffer.
+    ///
+    /// # Arguments
+    ///
+    /// * `str` - String to append to the buffer.
+    ///
+    /// # Examples
+    /// ```
+    /// use simplegen::CodeBuffer;
+    ///
+    /// let mut buffer = CodeBuffer::default();
+    /// buffer.println_right("Hello, World!");
+    /// // The string "    Hello, World!" is appended to the buffer, as the
+    /// // CodeBuffer::println_right() function will first increment the
+    /// // indentation level, then append the indented string to the buffer.
+    /// ```
+    pub fn println_right(&mut self, str: &str) -> &mut Self {
+        self.indent_right().println(str)
+    }
+
+    /// Indent left then print a string to the internal buffer.
+    ///
+    /// # Arguments
+    ///
+    /// * `str` - String to append to the buffer.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use simplegen::CodeBuffer;
+    ///
+    /// let mut buffer = CodeBuffer::default();
+    /// buffer.indent_right();
+    /// // Set the indentation level to 1.
+    ///
+    /// buffer.println_left("Hello, World!");
+    /// // Indentation level will be returned to 0, so "Hello, World!" will be
+    /// // appended to the buffer.
+    /// ```
+    pub fn println_left(&mut self, str: &str) -> &mut Self {
+        self.indent_left().println(str)
+    }
+
+    /// Append the lines of another buffer to this one, indenting each of them
+    /// by the current indentation level. Indentation already in the other
+    /// buffer's lines is kept, so its structure is preserved. Empty lines stay
+    /// empty. A line started by [CodeBuffer::print()] is ended first, and the
+    /// appended lines aren't wrapped by [CodeBuffer::set_max_width()].
+    ///
+    /// # Arguments
+    ///
+    /// * `other` - Buffer to copy lines from.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use simplegen::CodeBuffer;
+    ///
+    /// let mut body = CodeBuffer::default();
+    /// body.println("if x {");
+    /// body.println_right("return;");
+    /// body.println_left("}");
+    ///
+    /// let mut buffer = CodeBuffer::default();
+    /// buffer.println("fn main() {");
+    /// buffer.indent_right();
+    /// buffer.append(&body);
+    /// buffer.println_left("}");
+    ///
+    /// assert_eq!(
+    ///     "fn main() {\n    if x {\n        return;\n    }\n}",
+    ///     buffer.to_string()
+    /// );
+    /// ```
+    pub fn append(&mut self, other: &CodeBuffer) {
+        self.commit_pending();
+        for line in other.lines() {
+            if line.is_empty() {
+                self.push_raw_line(String::new());
+            } else {
+                let line = self.indent_line(line);
+                self.push_line(line);
+            }
+        }
+    }
+
+    /// Append the lines of another buffer to this one verbatim, ignoring the
+    /// current indentation level.
+    ///
+    /// # Arguments
+    ///
+    /// * `other` - Buffer to copy lines from.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use simplegen::CodeBuffer;
+    ///
+    /// let mut imports = CodeBuffer::default();
+    /// imports.println("use std::fmt;");
+    ///
+    /// let mut buffer = CodeBuffer::default();
+    /// buffer.indent_right();
+    /// buffer.append_raw(&imports);
+    ///
+    /// assert_eq!("use std::fmt;", buffer.to_string());
+    /// ```
+    pub fn append_raw(&mut self, other: &CodeBuffer) {
+        for line in other.lines() {
+            self.println_raw(line);
+        }
+    }
+
+    /// Write each of the given lines to the internal buffer at the current
+    /// indentation level.
+    ///
+    /// # Arguments
+    ///
+    /// * `lines` - Lines to append to the buffer.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use simplegen::CodeBuffer;
+    ///
+    /// let rendered: Vec<String> = vec!["x: 1,".to_string(), "y: 2,".to_string()];
+    ///
+    /// let mut buffer = CodeBuffer::default();
+    /// buffer.indent_right();
+    /// buffer.extend(&rendered);
+    ///
+    /// assert_eq!("    x: 1,\n    y: 2,", buffer.to_string());
+    /// ```
+    pub fn extend<I, S>(&mut self, lines: I)
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        self.println_each(lines);
+    }
+
+    /// Write each of the given lines to the internal buffer at the current
+    /// indentation level. This is the same as calling [CodeBuffer::println()]
+    /// for each line.
+    ///
+    /// # Arguments
+    ///
+    /// * `lines` - Lines to append to the buffer.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use simplegen::CodeBuffer;
+    ///
+    /// let mut buffer = CodeBuffer::default();
+    ///
+    /// buffer.println("fn main() {");
+    /// buffer.indent_right();
+    /// buffer.println_each(["let x = 1;", "let y = 2;"]);
+    /// buffer.println_left("}");
+    ///
+    /// assert_eq!(
+    ///     "fn main() {\n    let x = 1;\n    let y = 2;\n}",
+    ///     buffer.to_string()
+    /// );
+    /// ```
+    pub fn println_each<I, S>(&mut self, lines: I)
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        for line in lines {
+            self.println(line.as_ref());
+        }
+    }
+
+    /// Insert a line into the buffer before the line at `index`, indented at
+    /// the current indentation level. An `index` past the end of the buffer is
+    /// clamped, so the line is appended. Positions returned by
+    /// [CodeBuffer::mark()] at or after `index` move down with their lines.
+    ///
+    /// # Arguments
+    ///
+    /// * `index` - Position in the buffer to insert the line at.
+    /// * `str` - String to insert into the buffer.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use simplegen::CodeBuffer;
+    ///
+    /// let mut buffer = CodeBuffer::default();
+    /// buffer.println("struct Point {");
+    /// buffer.println("}");
+    ///
+    /// buffer.insert_line(0, "#[derive(Debug)]");
+    ///
+    /// assert_eq!("#[derive(Debug)]\nstruct Point {\n}", buffer.to_string());
+    /// ```
+    pub fn insert_line(&mut self, index: usize, str: &str) {
+        let index = index.min(self.buffer.len());
+        let line = self.indent_line(str);
+        self.buffer.insert(index, line);
+        self.marks = core::mem::take(&mut self.marks)
+            .into_iter()
+            .map(|(position, level)| {
+                if position >= index {
+                    (position + 1, level)
+                } else {
+                    (position, level)
+                }
+            })
+            .collect();
+    }
+
+    /// Insert a line at the start of the buffer, indented at the current
+    /// indentation level.
+    ///
+    /// # Arguments
+    ///
+    /// * `str` - String to insert into the buffer.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use simplegen::CodeBuffer;
+    ///
+    /// let mut buffer = CodeBuffer::default();
+    /// buffer.println("fn main() {}");
+    ///
+    /// buffer.prepend("use std::fmt;");
+    ///
+    /// assert_eq!("use std::fmt;\nfn main() {}", buffer.to_string());
+    /// ```
+    pub fn prepend(&mut self, str: &str) {
+        self.insert_line(0, str);
+    }
+
+    /// Remove the line most recently written to the buffer and return it. The
+    /// indentation level is left unchanged.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use simplegen::CodeBuffer;
+    ///
+    /// let mut buffer = CodeBuffer::default();
+    /// buffer.println("let x = 1;");
+    /// buffer.println("let y = 2;");
+    ///
+    /// assert_eq!(Some("let y = 2;".to_string()), buffer.pop_line());
+    /// assert_eq!("let x = 1;", buffer.to_string());
+    /// ```
+    pub fn pop_line(&mut self) -> Option<String> {
+        let line = self.buffer.pop()?;
+        self.marks.remove(&self.buffer.len());
+        Some(line)
+    }
+
+    /// Mark the position of the next line written to the buffer, so it can be
+    /// replaced later with [CodeBuffer::replace_line()]. The current
+    /// indentation level is recorded with the position.
+    ///
+    /// Inserting lines before the marked position moves the line it refers
+    /// to, so the position should be used before inserting lines.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use simplegen::CodeBuffer;
+    ///
+    /// let mut buffer = CodeBuffer::default();
+    /// buffer.println("fn main() {");
+    /// buffer.indent_right();
+    ///
+    /// let header = buffer.mark();
+    /// buffer.println("// Placeholder");
+    /// buffer.println("let x = 1;");
+    /// buffer.println("let y = 2;");
+    /// buffer.println_left("}");
+    ///
+    /// buffer.replace_line(header, "// 2 locals");
+    ///
+    /// assert_eq!(
+    ///     "fn main() {\n    // 2 locals\n    let x = 1;\n    let y = 2;\n}",
+    ///     buffer.to_string()
+    /// );
+    /// ```
+    pub fn mark(&mut self) -> usize {
+        let position = self.buffer.len();
+        self.marks.insert(position, self.level);
+        position
+    }
+
+    /// Replace the line at `position` in the buffer. If the position was
+    /// returned by [CodeBuffer::mark()], the new line is indented at the
+    /// level recorded by the mark, otherwise at the current indentation level.
+    ///
+    /// # Arguments
+    ///
+    /// * `position` - Position of the line in the buffer to replace.
+    /// * `str` - String to replace the line with.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there is no line at `position`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use simplegen::CodeBuffer;
+    ///
+    /// let mut buffer = CodeBuffer::default();
+    /// buffer.println("let x = 1;");
+    ///
+    /// buffer.replace_line(0, "let x = 2;");
+    ///
+    /// assert_eq!("let x = 2;", buffer.to_string());
+    /// ```
+    pub fn replace_line(&mut self, position: usize, str: &str) {
+        let line_count = self.buffer.len();
+        assert!(
+            position < line_count,
+            "position {} is out of bounds for a buffer of {} lines",
+            position,
+            line_count
+        );
+
+        let level = self.marks.get(&position).copied().unwrap_or(self.level);
+        let line = self.indent_line_at(level, str);
+        self.buffer[position] = line;
+    }
+
+    /// Replace every occurrence of `needle` in the lines of the buffer with
+    /// `replacement`. Only the content of each line is searched, so the
+    /// indentation at the start of a line is never changed, even if it's made
+    /// of an [IndentStyle::Unit] that isn't whitespace. This is useful
+    /// for filling in placeholders that are only known once the rest of the
+    /// code has been generated.
+    ///
+    /// # Arguments
+    ///
+    /// * `needle` - String to search for.
+    /// * `replacement` - String to replace each occurrence with.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use simplegen::CodeBuffer;
+    ///
+    /// let mut buffer = CodeBuffer::default();
+    /// buffer.println("struct __NAME__;");
+    ///
+    /// buffer.replace_all("__NAME__", "Parser");
+    ///
+    /// assert_eq!("struct Parser;", buffer.to_string());
+    /// ```
+    pub fn replace_all(&mut self, needle: &str, replacement: &str) {
+        if needle.is_empty() {
+            return;
+        }
+
+        for line in &mut self.buffer {
+            let prefix_len = self.style.content_start(line);
+            let content = &line[prefix_len..];
+            if content.contains(needle) {
+                let replaced = content.replace(needle, replacement);
+                line.truncate(prefix_len);
+                line.push_str(&replaced);
+            }
+        }
+    }
+
+    /// Comment out the lines of the buffer from `start` up to, but not
+    /// including, `end`, by inserting `prefix` after each line's indentation.
+    /// Empty lines are left empty. The range is clamped to the lines in the
+    /// buffer, so an empty or out of bounds range does nothing.
+    ///
+    /// # Arguments
+    ///
+    /// * `start` - Position of the first line to comment out.
+    /// * `end` - Position after the last line to comment out.
+    /// * `prefix` - Comment token to insert, including any space after it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use simplegen::CodeBuffer;
+    ///
+    /// let mut buffer = CodeBuffer::default();
+    /// buffer.println("fn main() {");
+    /// buffer.println_right("debug();");
+    /// buffer.println_left("}");
+    ///
+    /// buffer.comment_out_range(1, 2, "// ");
+    ///
+    /// assert_eq!("fn main() {\n    // debug();\n}", buffer.to_string());
+    /// ```
+    pub fn comment_out_range(&mut self, start: usize, end: usize, prefix: &str) {
+        let style = self.style.clone();
+        for line in self.lines_in_range(start, end) {
+            let content_start = style.content_start(line);
+            if content_start < line.len() {
+                line.insert_str(content_start, prefix);
+            }
+        }
+    }
+
+    /// Uncomment the lines of the buffer from `start` up to, but not
+    /// including, `end`, by removing `prefix` from after each line's
+    /// indentation. Lines that don't start with `prefix` are left as they are.
+    /// The range is clamped to the lines in the buffer.
+    ///
+    /// # Arguments
+    ///
+    /// * `start` - Position of the first line to uncomment.
+    /// * `end` - Position after the last line to uncomment.
+    /// * `prefix` - Comment token to remove, including any space after it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use simplegen::CodeBuffer;
+    ///
+    /// let mut buffer = CodeBuffer::default();
+    /// buffer.println("// let x = 1;");
+    ///
+    /// buffer.uncomment_range(0, 1, "// ");
+    ///
+    /// assert_eq!("let x = 1;", buffer.to_string());
+    /// ```
+    pub fn uncomment_range(&mut self, start: usize, end: usize, prefix: &str) {
+        let style = self.style.clone();
+        for line in self.lines_in_range(start, end) {
+            let content_start = style.content_start(line);
+            if line[content_start..].starts_with(prefix) {
+                line.replace_range(content_start..content_start + prefix.len(), "");
+            }
+        }
+    }
+
+    /// Wrap every line in the buffer in a block comment, by writing `open` as
+    /// the first line and `close` as the last line, both at column zero. If a
+    /// prefix has been set with [CodeBuffer::set_block_comment_line_prefix()],
+    /// it's written at the start of every line in between, before the
+    /// indentation. The preamble and epilogue aren't wrapped.
+    ///
+    /// # Arguments
+    ///
+    /// * `open` - Token that opens the block comment.
+    /// * `close` - Token that closes the block comment.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use simplegen::CodeBuffer;
+    ///
+    /// let mut buffer = CodeBuffer::default();
+    /// buffer.println("fn example() {");
+    /// buffer.println_left("}");
+    ///
+    /// buffer.wrap_in_block_comment("/*", "*/");
+    ///
+    /// assert_eq!("/*\nfn example() {\n}\n*/", buffer.to_string());
+    /// ```
+    pub fn wrap_in_block_comment(&mut self, open: &str, close: &str) {
+        self.commit_pending();
+
+        if !self.block_comment_line_prefix.is_empty() {
+            let prefix = self.block_comment_line_prefix.as_str();
+            for line in &mut self.buffer {
+                if line.is_empty() {
+                    line.push_str(prefix.trim_end_matches([' ', '\t']));
+                } else {
+                    line.insert_str(0, prefix);
+                }
+            }
+        }
+
+        self.buffer.insert(0, open.to_string());
+        self.buffer.push(close.to_string());
+        self.marks = core::mem::take(&mut self.marks)
+            .into_iter()
+            .map(|(position, level)| (position + 1, level))
+            .collect();
+    }
+
+    /// Normalize the lines in the buffer so regenerated output is stable.
+    /// Trailing spaces and tabs are trimmed from every line, and each run of
+    /// blank lines is collapsed into a single empty line. Lines with content
+    /// are never reordered or removed. Positions returned by
+    /// [CodeBuffer::mark()] move with their lines, and a mark on a line
+    /// that is removed is dropped.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use simplegen::CodeBuffer;
+    ///
+    /// let mut buffer = CodeBuffer::default();
+    /// buffer.println("fn first() {} ");
+    /// buffer.blank_line();
+    /// buffer.println_right("");
+    /// buffer.println_left("fn second() {}");
+    ///
+    /// buffer.normalize();
+    ///
+    /// assert_eq!("fn first() {}\n\nfn second() {}", buffer.to_string());
+    /// ```
+    pub fn normalize(&mut self) {
+        let mut previous_blank = false;
+        let mut removed = Vec::new();
+        let mut position = 0;
+        self.buffer.retain_mut(|line| {
+            let trimmed_len = line.trim_end_matches([' ', '\t']).len();
+            line.truncate(trimmed_len);
+
+            let blank = line.is_empty();
+            let keep = !(blank && previous_blank);
+            previous_blank = blank;
+            if !keep {
+                removed.push(position);
+            }
+            position += 1;
+            keep
+        });
+
+        if removed.is_empty() {
+            return;
+        }
+        self.marks = core::mem::take(&mut self.marks)
+            .into_iter()
+            .filter(|(position, _)| removed.binary_search(position).is_err())
+            .map(|(position, level)| {
+                let removed_before = removed.partition_point(|&index| index < position);
+                (position - removed_before, level)
+            })
+            .collect();
+    }
+
+    /// Remove blank lines, which are empty or only contain whitespace, from
+    /// the start of the buffer. Blank lines after the first line with content
+    /// are kept.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use simplegen::CodeBuffer;
+    ///
+    /// let mut buffer = CodeBuffer::default();
+    /// buffer.blank_line();
+    /// buffer.println("fn main() {}");
+    ///
+    /// buffer.trim_leading_blank_lines();
+    ///
+    /// assert_eq!("fn main() {}", buffer.to_string());
+    /// ```
+    pub fn trim_leading_blank_lines(&mut self) {
+        let blank = self
+            .buffer
+            .iter()
+            .take_while(|line| line.trim().is_empty())
+            .count();
+        if blank == 0 {
+            return;
+        }
+
+        self.buffer.drain(..blank);
+        self.marks = core::mem::take(&mut self.marks)
+            .into_iter()
+            .filter(|(position, _)| *position >= blank)
+            .map(|(position, level)| (position - blank, level))
+            .collect();
+    }
+
+    /// Remove blank lines, which are empty or only contain whitespace, from
+    /// the end of the buffer. Blank lines before the last line with content
+    /// are kept.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use simplegen::CodeBuffer;
+    ///
+    /// let mut buffer = CodeBuffer::default();
+    /// buffer.println("fn main() {}");
+    /// buffer.blank_line();
+    ///
+    /// buffer.trim_trailing_blank_lines();
+    ///
+    /// assert_eq!("fn main() {}", buffer.to_string());
+    /// ```
+    pub fn trim_trailing_blank_lines(&mut self) {
+        while self
+            .buffer
+            .last()
+            .is_some_and(|line| line.trim().is_empty())
+        {
+            self.pop_line();
+        }
+    }
+
+    /// Remove every line from the buffer and reset the indentation level to 0,
+    /// so the buffer can be reused. Settings such as the indentation style and
+    /// line ending are kept, as is the buffer's allocated capacity.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use simplegen::CodeBuffer;
+    ///
+    /// let mut buffer = CodeBuffer::default();
+    ///
+    /// for name in &["first", "second"] {
+    ///     buffer.println(&format!("fn {}() {{}}", name));
+    ///     // Write the file here.
+    ///     buffer.clear();
+    /// }
+    ///
+    /// assert_eq!("", buffer.to_string());
+    /// ```
+    pub fn clear(&mut self) {
+        self.buffer.clear();
+        self.pending = None;
+        self.incomplete_utf8.clear();
+        self.marks.clear();
+        self.level_stack.clear();
+        self.written_once.clear();
+        self.drained_lines = 0;
+        self.drained_blanks = 0;
+        self.level = 0;
+    }
+
+    /// Record the number of lines in the buffer, the indentation level, any
+    /// line started by [CodeBuffer::print()], the levels saved by
+    /// [CodeBuffer::push_level()] and the strings written by
+    /// [CodeBuffer::println_once()], so everything done afterwards can be
+    /// undone by [CodeBuffer::rollback()].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use simplegen::CodeBuffer;
+    ///
+    /// let mut buffer = CodeBuffer::default();
+    /// let checkpoint = buffer.checkpoint();
+    /// ```
+    pub fn checkpoint(&self) -> Checkpoint {
+        Checkpoint {
+            line_count: self.drained_lines + self.buffer.len(),
+            level: self.level,
+            pending: self.pending.clone(),
+            level_stack_len: self.level_stack.len(),
+            written_once_len: self.written_once.len(),
+        }
+    }
+
+    /// Discard every line written since `checkpoint` was made, and restore the
+    /// indentation level it recorded. A line started by [CodeBuffer::print()]
+    /// is put back the way it was when the checkpoint was made, levels saved
+    /// by [CodeBuffer::push_level()] since then are dropped, and strings
+    /// written by [CodeBuffer::println_once()] since then can be written
+    /// again.
+    ///
+    /// # Arguments
+    ///
+    /// * `checkpoint` - Checkpoint returned by [CodeBuffer::checkpoint()].
+    ///
+    /// # Panics
+    ///
+    /// Panics if the buffer has fewer lines than when the checkpoint was made,
+    /// as rolling back can't add lines. Lines written out by
+    /// [CodeBuffer::drain_to_writer()] still count, but they can't be taken
+    /// back, so rolling back to a checkpoint made before a drain only
+    /// discards the lines still in the buffer.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use simplegen::CodeBuffer;
+    ///
+    /// let mut buffer = CodeBuffer::default();
+    /// buffer.println("let x = 1;");
+    ///
+    /// let checkpoint = buffer.checkpoint();
+    /// buffer.println("let y = 2;");
+    /// buffer.rollback(checkpoint);
+    ///
+    /// assert_eq!("let x = 1;", buffer.to_string());
+    /// ```
+    pub fn rollback(&mut self, checkpoint: Checkpoint) {
+        let line_count = self.drained_lines + self.buffer.len();
+        assert!(
+            checkpoint.line_count <= line_count,
+            "checkpoint at {} lines is ahead of a buffer of {} lines",
+            checkpoint.line_count,
+            line_count
+        );
+
+        let kept = checkpoint.line_count.saturating_sub(self.drained_lines);
+        self.buffer.truncate(kept);
+        self.marks.split_off(&(kept + 1));
+        self.pending = checkpoint.pending;
+        self.incomplete_utf8.clear();
+        self.level = checkpoint.level;
+        self.level_stack.truncate(checkpoint.level_stack_len);
+        let written_once_len = checkpoint.written_once_len;
+        self.written_once
+            .retain(|_, order| *order < written_once_len);
+    }
+
+    /// Write a line to the internal buffer at the current indentation level,
+    /// but only if `cond` is true.
+    ///
+    /// # Arguments
+    ///
+    /// * `cond` - Whether to write the line.
+    /// * `str` - String to append to the buffer.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use simplegen::CodeBuffer;
+    ///
+    /// let public = false;
+    /// let mut buffer = CodeBuffer::default();
+    ///
+    /// buffer
+    ///     .println_if(public, "#[no_mangle]")
+    ///     .println_if(true, "fn main() {}");
+    ///
+    /// assert_eq!("fn main() {}", buffer.to_string());
+    /// ```
+    pub fn println_if(&mut self, cond: bool, str: &str) -> &mut Self {
+        if cond {
+            self.println(str);
+        }
+        self
+    }
+
+    /// Indent right then print a string to the internal buffer, but only if
+    /// `cond` is true. If `cond` is false, the indentation level is left
+    /// unchanged too.
+    ///
+    /// # Arguments
+    ///
+    /// * `cond` - Whether to indent and write the line.
+    /// * `str` - String to append to the buffer.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use simplegen::CodeBuffer;
+    ///
+    /// let mut buffer = CodeBuffer::default();
+    ///
+    /// buffer.println_if(true, "{");
+    /// buffer.println_right_if(true, "\"number\": 1");
+    /// buffer.println_left_if(true, "}");
+    ///
+    /// assert_eq!("{\n    \"number\": 1\n}", buffer.to_string());
+    /// ```
+    pub fn println_right_if(&mut self, cond: bool, str: &str) -> &mut Self {
+        if cond {
+            self.println_right(str);
+        }
+        self
+    }
+
+    /// Indent left then print a string to the internal buffer, but only if
+    /// `cond` is true. If `cond` is false, the indentation level is left
+    /// unchanged too.
+    ///
+    /// # Arguments
+    ///
+    /// * `cond` - Whether to indent and write the line.
+    /// * `str` - String to append to the buffer.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use simplegen::CodeBuffer;
+    ///
+    /// let mut buffer = CodeBuffer::default();
+    /// buffer.indent_right();
+    ///
+    /// buffer.println_left_if(false, "}");
+    ///
+    /// assert_eq!(1, buffer.level());
+    /// ```
+    pub fn println_left_if(&mut self, cond: bool, str: &str) -> &mut Self {
+        if cond {
+            self.println_left(str);
+        }
+        self
+    }
+
+    /// Join the lines in the buffer with a custom separator, instead of the
+    /// configured line ending. Each line keeps its indentation, and no
+    /// separator is added after the last line.
+    ///
+    /// # Arguments
+    ///
+    /// * `sep` - Separator to place between lines.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use simplegen::CodeBuffer;
+    ///
+    /// let mut buffer = CodeBuffer::default();
+    /// buffer.println("let x = 1;");
+    /// buffer.println("let y = 2;");
+    ///
+    /// assert_eq!("let x = 1; let y = 2;", buffer.join_with(" "));
+    /// ```
+    pub fn join_with(&self, sep: &str) -> String {
+        self.output_lines().collect::<Vec<&str>>().join(sep)
+    }
+
+    /// Get the lines that `to_string()` joins, including any preamble,
+    /// epilogue and pending line, with output settings applied.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use simplegen::CodeBuffer;
+    ///
+    /// let mut buffer = CodeBuffer::default();
+    /// buffer.println("fn main() {");
+    /// buffer.println_right("run();");
+    /// buffer.println_left("}");
+    ///
+    /// assert_eq!(vec!["fn main() {", "    run();", "}"], buffer.to_lines());
+    /// ```
+    pub fn to_lines(&self) -> Vec<String> {
+        self.output_lines().map(str::to_string).collect()
+    }
+
+    /// Consume the buffer and return the lines that `to_string()` would join,
+    /// without copying them.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use simplegen::CodeBuffer;
+    ///
+    /// let mut buffer = CodeBuffer::default();
+    /// buffer.println("let x = 1;");
+    ///
+    /// assert_eq!(vec!["let x = 1;"], buffer.into_lines());
+    /// ```
+    pub fn into_lines(self) -> Vec<String> {
+        let mut lines = self.preamble;
+        lines.extend(self.buffer);
+        lines.extend(self.pending);
+        lines.extend(self.epilogue);
+
+        if self.trim_trailing_whitespace {
+            for line in &mut lines {
+                let trimmed_len = line.trim_end_matches([' ', '\t']).len();
+                line.truncate(trimmed_len);
+            }
+        }
+        lines
+    }
+
+    /// Consume the buffer and return its contents as a string, the same as
+    /// `to_string()`. The first line's allocation is reused for the whole
+    /// string, so only the lines after it are copied.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use simplegen::CodeBuffer;
+    ///
+    /// let mut buffer = CodeBuffer::default();
+    /// buffer.println("fn main() {}");
+    ///
+    /// assert_eq!("fn main() {}", buffer.into_string());
+    /// ```
+    pub fn into_string(self) -> String {
+        let separator = self.line_ending.as_str();
+        let trailing_newline = self.trailing_newline;
+        let lines = self.into_lines();
+
+        let separators = if trailing_newline {
+            lines.len()
+        } else {
+            lines.len().saturating_sub(1)
+        };
+        let total_len = lines.iter().map(String::len).sum::<usize>() + separators * separator.len();
+
+        let mut lines = lines.into_iter();
+        let mut string = match lines.next() {
+            Some(first) => first,
+            None => return String::new(),
+        };
+        string.reserve(total_len - string.len());
+        for line in lines {
+            string.push_str(separator);
+            string.push_str(&line);
+        }
+        if trailing_newline {
+            string.push_str(separator);
+        }
+        string
+    }
+
+    /// Get the output of the buffer with a 1-based line number in front of
+    /// each line, right-aligned in a gutter wide enough for the last number.
+    /// This is meant for debugging generated code, so line numbers in errors
+    /// from other tools are easy to find.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use simplegen::CodeBuffer;
+    ///
+    /// let mut buffer = CodeBuffer::default();
+    /// buffer.println("fn main() {");
+    /// buffer.println_right("run();");
+    /// buffer.println_left("}");
+    ///
+    /// assert_eq!(
+    ///     "1 | fn main() {\n2 |     run();\n3 | }",
+    ///     buffer.to_string_numbered()
+    /// );
+    /// ```
+    pub fn to_string_numbered(&self) -> String {
+        let lines: Vec<&str> = self.output_lines().collect();
+        let width = lines.len().to_string().len();
+
+        lines
+            .iter()
+            .enumerate()
+            .map(|(index, line)| {
+                if line.is_empty() {
+                    format!("{:>width$} |", index + 1, width = width)
+                } else {
+                    format!("{:>width$} | {}", index + 1, line, width = width)
+                }
+            })
+            .collect::<Vec<String>>()
+            .join(self.line_ending.as_str())
+    }
+
+    /// Split the output of the buffer into strings of at most
+    /// `lines_per_chunk` lines each, in order. Only the last chunk can be
+    /// shorter. Each chunk is joined like `to_string()`, including a trailing
+    /// newline if one is set, so every chunk can be written as a file of its
+    /// own. If `lines_per_chunk` is 0, the whole output is returned as a
+    /// single chunk. An empty buffer returns no chunks.
+    ///
+    /// # Arguments
+    ///
+    /// * `lines_per_chunk` - Maximum number of lines in each chunk.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use simplegen::CodeBuffer;
+    ///
+    /// let mut buffer = CodeBuffer::default();
+    /// buffer.println("a");
+    /// buffer.println("b");
+    /// buffer.println("c");
+    ///
+    /// assert_eq!(vec!["a\nb", "c"], buffer.chunks(2));
+    /// ```
+    pub fn chunks(&self, lines_per_chunk: usize) -> Vec<String> {
+        let separator = self.line_ending.as_str();
+        let lines: Vec<&str> = self.output_lines().collect();
+        let lines_per_chunk = if lines_per_chunk == 0 {
+            lines.len().max(1)
+        } else {
+            lines_per_chunk
+        };
+
+        lines
+            .chunks(lines_per_chunk)
+            .map(|chunk| {
+                let mut string = chunk.join(separator);
+                if self.trailing_newline {
+                    string.push_str(separator);
+                }
+                string
+            })
+            .collect()
+    }
+
+    /// Compare the output of this buffer with the output of `other`, line by
+    /// line. Lines only in `other` are [DiffLine::Added], and lines only in
+    /// this buffer are [DiffLine::Removed]. The diff is based on the longest
+    /// common subsequence of the lines, so it's as short as possible.
+    ///
+    /// # Arguments
+    ///
+    /// * `other` - Buffer to compare this buffer with.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use simplegen::{CodeBuffer, DiffLine};
+    ///
+    /// let mut expected = CodeBuffer::default();
+    /// expected.println("use std::fmt;");
+    /// let mut actual = CodeBuffer::default();
+    /// actual.println("use std::fmt;");
+    /// actual.println("use std::io;");
+    ///
+    /// assert_eq!(
+    ///     vec![
+    ///         DiffLine::Same("use std::fmt;".to_string()),
+    ///         DiffLine::Added("use std::io;".to_string()),
+    ///     ],
+    ///     expected.diff(&actual)
+    /// );
+    /// ```
+    pub fn diff(&self, other: &CodeBuffer) -> Vec<DiffLine> {
+        let old: Vec<&str> = self.output_lines().collect();
+        let new: Vec<&str> = other.output_lines().collect();
+        diff_lines(&old, &new)
+    }
+
+    /// Get the diff from [CodeBuffer::diff()] as text, with each line
+    /// prefixed by `+` if it was added, `-` if it was removed, or a space if
+    /// it's the same in both buffers. This is meant for showing why a
+    /// snapshot test failed.
+    ///
+    /// # Arguments
+    ///
+    /// * `other` - Buffer to compare this buffer with.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use simplegen::CodeBuffer;
+    ///
+    /// let mut expected = CodeBuffer::default();
+    /// expected.println("let x = 1;");
+    /// let mut actual = CodeBuffer::default();
+    /// actual.println("let x = 2;");
+    ///
+    /// assert_eq!("-let x = 1;\n+let x = 2;", expected.diff_string(&actual));
+    /// ```
+    pub fn diff_string(&self, other: &CodeBuffer) -> String {
+        self.diff(other)
+            .iter()
+            .map(|line| match line {
+                DiffLine::Same(line) => format!(" {}", line),
+                DiffLine::Added(line) => format!("+{}", line),
+                DiffLine::Removed(line) => format!("-{}", line),
+            })
+            .collect::<Vec<String>>()
+            .join(self.line_ending.as_str())
+    }
+
+    #[cfg(feature = "std")]
+    /// Write the contents of the buffer to a file.
+    ///
+    /// Returns the number of bytes written.
+    ///
+    /// # Arguments
+    ///
+    /// * `file` - File to write the contents of the buffer to.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use simplegen::CodeBuffer;
+    /// use std::fs::File;
+    ///
+    /// let mut buffer = CodeBuffer::default();
+    /// buffer.println("Hello, World!");
+    ///
+    /// let mut file = File::create("hello.txt").unwrap();
+    /// buffer.flush_to_file(&mut file).unwrap();
+    /// ```
+    pub fn flush_to_file(&self, file: &mut File) -> io::Result<usize> {
+        self.flush_to_writer(file)
+    }
+
+    #[cfg(feature = "std")]
+    /// Write the contents of the buffer to the file at `path`. The file is
+    /// created if it doesn't exist, and truncated if it does.
+    ///
+    /// Returns the number of bytes written.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Path of the file to write the contents of the buffer to.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use simplegen::CodeBuffer;
+    ///
+    /// let mut buffer = CodeBuffer::default();
+    /// buffer.println("Hello, World!");
+    ///
+    /// buffer.write_to_path("hello.txt").unwrap();
+    /// ```
+    pub fn write_to_path<P: AsRef<Path>>(&self, path: P) -> io::Result<usize> {
+        let mut file = File::create(path)?;
+        self.flush_to_file(&mut file)
+    }
+
+    #[cfg(feature = "std")]
+    /// Write the contents of the buffer to any [io::Write].
+    ///
+    /// Returns the number of bytes written.
+    ///
+    /// # Arguments
+    ///
+    /// * `writer` - Writer to write the contents of the buffer to.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use simplegen::CodeBuffer;
+    ///
+    /// let mut buffer = CodeBuffer::default();
+    /// buffer.println("Hello, World!");
+    ///
+    /// let mut bytes: Vec<u8> = Vec::new();
+    /// buffer.flush_to_writer(&mut bytes).unwrap();
+    ///
+    /// assert_eq!(b"Hello, World!", bytes.as_slice());
+    /// ```
+    pub fn flush_to_writer<W: Write>(&self, writer: &mut W) -> io::Result<usize> {
+        let string = self.to_string();
+        writer.write_all(string.as_bytes())?;
+        Ok(string.len())
+    }
+
+    #[cfg(feature = "std")]
+    /// Write the lines in the buffer to any [io::Write], each followed by the
+    /// line ending, then remove them from the buffer to free memory. The
+    /// indentation level and settings are kept, so generation can carry on
+    /// and be drained again later. Draining everything gives the same output
+    /// as `to_string()` with a trailing newline.
+    ///
+    /// A line started by [CodeBuffer::print()] or `write!` stays in the
+    /// buffer until it is ended. The preamble and epilogue aren't written.
+    /// Positions returned by [CodeBuffer::mark()] can't be used after
+    /// draining. Blank lines at the end of the drained output still count
+    /// towards [CodeBuffer::set_max_consecutive_blanks()], and a
+    /// [CodeBuffer::checkpoint()] made before draining can still be rolled
+    /// back to, though the lines already written stay written.
+    ///
+    /// Returns the number of bytes written.
+    ///
+    /// # Arguments
+    ///
+    /// * `writer` - Writer to write lines to.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use simplegen::CodeBuffer;
+    ///
+    /// let mut bytes: Vec<u8> = Vec::new();
+    /// let mut buffer = CodeBuffer::default();
+    ///
+    /// buffer.println("fn main() {");
+    /// buffer.indent_right();
+    /// buffer.drain_to_writer(&mut bytes).unwrap();
+    ///
+    /// buffer.println_left("}");
+    /// buffer.drain_to_writer(&mut bytes).unwrap();
+    ///
+    /// assert!(buffer.is_empty());
+    /// assert_eq!(b"fn main() {\n}\n", bytes.as_slice());
+    /// ```
+    pub fn drain_to_writer<W: Write>(&mut self, writer: &mut W) -> io::Result<usize> {
+        let separator = self.line_ending.as_str();
+        let mut written = 0;
+
+        for line in &self.buffer {
+            let line = if self.trim_trailing_whitespace {
+                line.trim_end_matches([' ', '\t'])
+            } else {
+                line
+            };
+            writer.write_all(line.as_bytes())?;
+            writer.write_all(separator.as_bytes())?;
+            written += line.len() + separator.len();
+        }
+
+        let trailing_blanks = self
+            .buffer
+            .iter()
+            .rev()
+            .take_while(|line| line.trim().is_empty())
+            .count();
+        if trailing_blanks == self.buffer.len() {
+            self.drained_blanks += trailing_blanks;
+        } else {
+            self.drained_blanks = trailing_blanks;
+        }
+        self.drained_lines += self.buffer.len();
+        self.buffer.clear();
+        self.marks.clear();
+        Ok(written)
+    }
+
+    #[cfg(feature = "rustfmt")]
+    /// Format the contents of the buffer as Rust source code by piping it
+    /// through `rustfmt`, and return the formatted source. This needs the
+    /// `rustfmt` feature, and `rustfmt` to be installed and on the `PATH`.
+    ///
+    /// Returns an error of kind [io::ErrorKind::NotFound] if `rustfmt` can't
+    /// be found, or [io::ErrorKind::InvalidData] with `rustfmt`'s error
+    /// message if it fails to format the code.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use simplegen::CodeBuffer;
+    ///
+    /// let mut buffer = CodeBuffer::default();
+    /// buffer.println("fn main(){let x=1;}");
+    ///
+    /// let formatted = buffer.to_formatted_rust().unwrap();
+    ///
+    /// assert_eq!("fn main() {\n    let x = 1;\n}\n", formatted);
+    /// ```
+    pub fn to_formatted_rust(&self) -> io::Result<String> {
+        let mut child = Command::new("rustfmt")
+            .args(["--edition", "2021", "--emit", "stdout"])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|error| match error.kind() {
+                io::ErrorKind::NotFound => io::Error::new(
+                    io::ErrorKind::NotFound,
+                    "rustfmt was not found, is it installed and on the PATH?",
+                ),
+                _ => error,
+            })?;
+
+        // Write on another thread so a full stdout pipe can't block the write.
+        let mut stdin = child.stdin.take().expect("stdin is piped");
+        let source = self.to_string();
+        let writer = std::thread::spawn(move || stdin.write_all(source.as_bytes()));
+
+        let output = child.wait_with_output()?;
+        writer.join().expect("writing to rustfmt panicked")?;
+
+        if !output.status.success() {
+            let message = String::from_utf8_lossy(&output.stderr).into_owned();
+            return Err(io::Error::new(io::ErrorKind::InvalidData, message));
+        }
+        String::from_utf8(output.stdout)
+            .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))
+    }
+
+    /// Indent right and return an [IndentGuard] that indents left again when
+    /// it is dropped.
+    ///
+    /// The guard borrows the buffer mutably, and dereferences to it, so lines
+    /// are written through the guard while it is alive.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use simplegen::CodeBuffer;
+    ///
+    /// let mut buffer = CodeBuffer::default();
+    ///
+    /// buffer.println("{");
+    /// {
+    ///     let mut guard = buffer.indented();
+    ///     guard.println("\"number\": 1");
+    /// }
+    /// // The guard has been dropped, so the indentation level is back to 0.
+    /// buffer.println("}");
+    /// ```
+    pub fn indented(&mut self) -> IndentGuard<'_> {
+        IndentGuard::new(self)
+    }
+
+    /// Open a block by writing `"{header} {"` and indenting right. The
+    /// returned [BlockGuard] indents left and writes `"}"` when it is dropped.
+    /// With [BraceStyle::Allman], the opening brace is written on a line of
+    /// its own instead.
+    ///
+    /// # Arguments
+    ///
+    /// * `header` - Code that comes before the opening brace.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use simplegen::CodeBuffer;
+    ///
+    /// let mut buffer = CodeBuffer::default();
+    ///
+    /// {
+    ///     let mut block = buffer.block("fn add_one(x: u64) -> u64");
+    ///     block.println("x + 1");
+    /// }
+    ///
+    /// assert_eq!("fn add_one(x: u64) -> u64 {\n    x + 1\n}", buffer.to_string());
+    /// ```
+    pub fn block(&mut self, header: &str) -> BlockGuard<'_> {
+        self.block_with_close(header, "}")
+    }
+
+    /// Open a block like [CodeBuffer::block()], but write `close` instead of
+    /// `"}"` when the block is closed. This is useful for literals that end in
+    /// `};` or `},`. The opening brace is placed according to
+    /// [CodeBuffer::set_brace_style()].
+    ///
+    /// # Arguments
+    ///
+    /// * `header` - Code that comes before the opening brace.
+    /// * `close` - Line written when the block is closed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use simplegen::CodeBuffer;
+    ///
+    /// let mut buffer = CodeBuffer::default();
+    ///
+    /// {
+    ///     let mut block = buffer.block_with_close("let point = Point", "};");
+    ///     block.println("x: 1,");
+    ///     block.println("y: 2,");
+    /// }
+    ///
+    /// assert_eq!(
+    ///     "let point = Point {\n    x: 1,\n    y: 2,\n};",
+    ///     buffer.to_string()
+    /// );
+    /// ```
+    pub fn block_with_close(&mut self, header: &str, close: &str) -> BlockGuard<'_> {
+        match self.brace_style {
+            BraceStyle::KAndR => self.delimited(header, " {", close),
+            BraceStyle::Allman => {
+                self.println(header);
+                BlockGuard::new(self, "{", close)
+            }
+        }
+    }
+
+    /// Open a block by writing `"{header}{open}"` and indenting right. The
+    /// returned [BlockGuard] indents left and writes `close` when it is
+    /// dropped. This is useful for blocks that aren't delimited by braces,
+    /// such as array literals.
+    ///
+    /// # Arguments
+    ///
+    /// * `header` - Code that comes before the opening delimiter.
+    /// * `open` - Opening delimiter, written straight after the header.
+    /// * `close` - Line written when the block is closed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use simplegen::CodeBuffer;
+    ///
+    /// let mut buffer = CodeBuffer::default();
+    ///
+    /// {
+    ///     let mut array = buffer.delimited("let primes =", " [", "];");
+    ///     array.println("2,");
+    ///     array.println("3,");
+    /// }
+    ///
+    /// assert_eq!("let primes = [\n    2,\n    3,\n];", buffer.to_string());
+    /// ```
+    pub fn delimited(&mut self, header: &str, open: &str, close: &str) -> BlockGuard<'_> {
+        BlockGuard::new(self, &format!("{}{}", header, open), close)
+    }
+
+    /// Open a named, foldable region by writing its opening marker at the
+    /// current indentation level. The returned [RegionGuard] writes the
+    /// closing marker when it is dropped. The markers are set with
+    /// [CodeBuffer::set_region_markers()].
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - Name of the region.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use simplegen::CodeBuffer;
+    ///
+    /// let mut buffer = CodeBuffer::default();
+    ///
+    /// {
+    ///     let mut region = buffer.region("Imports");
+    ///     region.println("use std::fmt;");
+    /// }
+    ///
+    /// assert_eq!(
+    ///     "// region: Imports\nuse std::fmt;\n// endregion",
+    ///     buffer.to_string()
+    /// );
+    /// ```
+    pub fn region(&mut self, name: &str) -> RegionGuard<'_> {
+        let open = self.region_markers.0.replace("{name}", name);
+        let close = self.region_markers.1.replace("{name}", name);
+        RegionGuard::new(self, &open, &close)
+    }
+
+    /// Write the start tag of an XML or HTML element, and return an
+    /// [ElementGuard] that indents the element's content and writes the end
+    /// tag when it is dropped. Attribute values are quoted and escaped.
+    ///
+    /// # Arguments
+    ///
+    /// * `tag` - Name of the element.
+    /// * `attrs` - Names and values of the element's attributes.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use simplegen::CodeBuffer;
+    ///
+    /// let mut buffer = CodeBuffer::new(2);
+    /// {
+    ///     let mut link = buffer.element("a", &[("href", "/?a=1&b=2")]);
+    ///     link.println("Home");
+    /// }
+    ///
+    /// assert_eq!(
+    ///     "<a href=\"/?a=1&amp;b=2\">\n  Home\n</a>",
+    ///     buffer.to_string()
+    /// );
+    /// ```
+    pub fn element(&mut self, tag: &str, attrs: &[(&str, &str)]) -> ElementGuard<'_> {
+        let start_tag = format!("<{}{}>", tag, render_attrs(attrs));
+        ElementGuard::new(self, &start_tag, tag)
+    }
+
+    /// Write a self-closing XML or HTML element, which has no content, to the
+    /// internal buffer at the current indentation level. Attribute values are
+    /// quoted and escaped.
+    ///
+    /// # Arguments
+    ///
+    /// * `tag` - Name of the element.
+    /// * `attrs` - Names and values of the element's attributes.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use simplegen::CodeBuffer;
+    ///
+    /// let mut buffer = CodeBuffer::default();
+    /// buffer.void_element("img", &[("src", "logo.png"), ("alt", "Logo")]);
+    ///
+    /// assert_eq!("<img src=\"logo.png\" alt=\"Logo\" />", buffer.to_string());
+    /// ```
+    pub fn void_element(&mut self, tag: &str, attrs: &[(&str, &str)]) -> &mut Self {
+        let line = format!("<{}{} />", tag, render_attrs(attrs));
+        self.println(&line)
+    }
+
+    /// Create a [ListBuilder] that writes the items of a list to the buffer,
+    /// one per line, with commas between them.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use simplegen::CodeBuffer;
+    ///
+    /// let mut buffer = CodeBuffer::default();
+    /// buffer.list().item("a").item("b").finish();
+    ///
+    /// assert_eq!("a,\nb", buffer.to_string());
+    /// ```
+    pub fn list(&mut self) -> ListBuilder<'_> {
+        ListBuilder::new(self)
+    }
+
+    /// Create a [CounterList] that writes numbered items to the buffer,
+    /// counting up from `start`.
+    ///
+    /// # Arguments
+    ///
+    /// * `start` - Number of the first item.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use simplegen::CodeBuffer;
+    ///
+    /// let mut buffer = CodeBuffer::default();
+    /// buffer.numbered_list(1).item("first").item("second");
+    ///
+    /// assert_eq!("1. first\n2. second", buffer.to_string());
+    /// ```
+    pub fn numbered_list(&mut self, start: usize) -> CounterList<'_> {
+        CounterList::new(self, start)
+    }
+
+    /// Open a Rust `match` on `scrutinee`, and return a [MatchBuilder] that
+    /// writes its arms. The opening brace is placed according to
+    /// [CodeBuffer::set_brace_style()].
+    ///
+    /// # Arguments
+    ///
+    /// * `scrutinee` - Expression to match on.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use simplegen::CodeBuffer;
+    ///
+    /// let mut buffer = CodeBuffer::new(4);
+    /// buffer.match_on("x").arm_expr("_", "()").finish();
+    ///
+    /// assert_eq!("match x {\n    _ => (),\n}", buffer.to_string());
+    /// ```
+    pub fn match_on(&mut self, scrutinee: &str) -> MatchBuilder<'_> {
+        MatchBuilder::new(self, scrutinee)
+    }
+
+    /// Open a C-style `switch` on `expr`, and return a [SwitchBuilder] that
+    /// writes its cases. The opening brace is placed according to
+    /// [CodeBuffer::set_brace_style()].
+    ///
+    /// # Arguments
+    ///
+    /// * `expr` - Expression to switch on, without parentheses.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use simplegen::CodeBuffer;
+    ///
+    /// let mut buffer = CodeBuffer::new(4);
+    /// buffer.switch_on("x").case("0", |_| {}, true).finish();
+    ///
+    /// assert_eq!("switch (x) {\n    case 0:\n        break;\n}", buffer.to_string());
+    /// ```
+    pub fn switch_on(&mut self, expr: &str) -> SwitchBuilder<'_> {
+        SwitchBuilder::new(self, expr)
+    }
+
+    /// Create an empty buffer at the current indentation level, with the same
+    /// settings as this buffer apart from the preamble and epilogue.
+    fn sub_buffer(&self) -> CodeBuffer {
+        let mut sub_buffer = CodeBuffer::with_style(self.style.clone());
+        sub_buffer.level = self.level;
+        sub_buffer.base_level = self.base_level;
+        sub_buffer.line_ending = self.line_ending;
+        sub_buffer.trailing_newline = self.trailing_newline;
+        sub_buffer.brace_style = self.brace_style;
+        sub_buffer.trim_trailing_whitespace = self.trim_trailing_whitespace;
+        sub_buffer.indent_blank_lines = self.indent_blank_lines;
+        sub_buffer.max_consecutive_blanks = self.max_consecutive_blanks;
+        sub_buffer.strict_single_line = self.strict_single_line;
+        sub_buffer.comment_prefix = self.comment_prefix.clone();
+        sub_buffer.block_comment_line_prefix = self.block_comment_line_prefix.clone();
+        sub_buffer.region_markers = self.region_markers.clone();
+        sub_buffer.annotation_style = self.annotation_style.clone();
+        sub_buffer.max_width = self.max_width;
+        sub_buffer.max_indent_columns = self.max_indent_columns;
+        sub_buffer
+    }
+
+    /// Get the lines of the buffer from `start` up to, but not including,
+    /// `end`, with both clamped to the number of lines.
+    fn lines_in_range(&mut self, start: usize, end: usize) -> &mut [String] {
+        let end = end.min(self.buffer.len());
+        let start = start.min(end);
+        &mut self.buffer[start..end]
+    }
+
+    /// Get the number of characters in the indentation for a level.
+    fn prefix_width(&self, level: u32) -> usize {
+        (self.style.unit_width() as usize).saturating_mul(self.output_level(level) as usize)
+    }
+
+    /// Get the level that lines at `level` are indented by in the output. This
+    /// is `level` on top of the base level, capped so the indentation is no
+    /// wider than the maximum number of indentation columns, or 0 if
+    /// indentation is turned off.
+    fn output_level(&self, level: u32) -> u32 {
+        if self.no_indent {
+            return 0;
+        }
+
+        let level = self.base_level.saturating_add(level);
+        match self.style.unit_width() {
+            // Every level is empty, so there is nothing to write.
+            0 => 0,
+            width => {
+                let max_level = self.max_indent_columns / width as usize;
+                (level as usize).min(max_level) as u32
+            }
+        }
+    }
+
+    /// Prefix a string with the indentation for the current level.
+    fn indent_line(&self, str: &str) -> String {
+        self.indent_line_at(self.level, str)
+    }
+
+    /// Prefix a string with the indentation for a level, on top of the base
+    /// level.
+    fn indent_line_at(&self, level: u32, str: &str) -> String {
+        let level = self.output_level(level);
+        let mut line = String::with_capacity(self.style.prefix_len(level) + str.len());
+        self.style.push_prefix(level, &mut line);
+        line.push_str(str);
+        line
+    }
+
+    /// Iterate over the lines that make up the output of the buffer, including
+    /// any pending line, preamble and epilogue, with output settings applied.
+    fn output_lines(&self) -> impl Iterator<Item = &str> {
+        let trim = self.trim_trailing_whitespace;
+        self.preamble
+            .iter()
+            .chain(&self.buffer)
+            .map(String::as_str)
+            .chain(self.pending.as_deref())
+            .chain(self.epilogue.iter().map(String::as_str))
+            .map(move |line| {
+                if trim {
+                    line.trim_end_matches([' ', '\t'])
+                } else {
+                    line
+                }
+            })
+    }
+
+    /// Get the pending line, starting it at the current indentation level if
+    /// there isn't one.
+    fn pending_line(&mut self) -> &mut String {
+        let level = self.output_level(self.level);
+        let style = &self.style;
+        self.pending.get_or_insert_with(|| style.prefix(level))
+    }
+
+    /// Panic if strict single line mode is on and `str` contains a newline.
+    fn assert_single_line(&self, str: &str) {
+        assert!(
+            !(self.strict_single_line && str.contains('\n')),
+            "println was given a string containing a newline, use println_block to write several lines"
+        );
+    }
+
+    /// Write `header` followed by an opening brace, placed according to the
+    /// brace style, then indent right.
+    pub(crate) fn open_brace(&mut self, header: &str) {
+        match self.brace_style {
+            BraceStyle::KAndR => self.println(&format!("{} {{", header)),
+            BraceStyle::Allman => self.println(header).println("{"),
+        };
+        self.indent_right();
+    }
+
+    /// Push a finished line into the buffer, emptying it first if it's blank
+    /// and blank lines aren't indented.
+    fn push_line(&mut self, mut line: String) {
+        if !self.indent_blank_lines && line.trim().is_empty() {
+            line.clear();
+        }
+        self.push_raw_line(line);
+    }
+
+    /// Push a line into the buffer as it is, unless it's blank and the buffer
+    /// already ends with the maximum number of consecutive blank lines.
+    fn push_raw_line(&mut self, line: String) {
+        if let Some(max) = self.max_consecutive_blanks {
+            let is_blank = |line: &String| line.trim().is_empty();
+            let mut trailing_blanks = self
+                .buffer
+                .iter()
+                .rev()
+                .take_while(|line| is_blank(line))
+                .count();
+            if trailing_blanks == self.buffer.len() {
+                trailing_blanks += self.drained_blanks;
+            }
+            if is_blank(&line) && trailing_blanks >= max {
+                return;
+            }
+        }
+        self.buffer.push(line);
+    }
+
+    /// Move the pending line, if there is one, into the buffer, in the same
+    /// way as lines written by [CodeBuffer::println()].
+    fn commit_pending(&mut self) {
+        if let Some(pending) = self.pending.take() {
+            self.push_line(pending);
+        }
+    }
+}
+
+/// Render attributes of an XML or HTML element, each preceded by a space.
+fn render_attrs(attrs: &[(&str, &str)]) -> String {
+    attrs
+        .iter()
+        .map(|(name, value)| format!(" {}={}", name, escape_xml_attr(value)))
+        .collect()
+}
+
+/// Split text into owned lines on `\n`. An empty string has no lines.
+fn split_text_lines(text: &str) -> Vec<String> {
+    if text.is_empty() {
+        Vec::new()
+    } else {
+        text.split('\n').map(str::to_string).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::code_buffer::CodeBuffer;
+    use crate::escape::escape_rust_str;
+    use crate::{
+        println_fmt, BraceStyle, DiffLine, EmptyBufferError, IndentStyle, LineEnding,
+        TemplateContext, UnbalancedIndentError,
+    };
+    use std::fmt::Write;
+    #[cfg(feature = "std")]
+    use std::fs::{self, File};
+    #[cfg(feature = "std")]
+    use std::io;
+
+    #[test]
+    fn should_write_to_buffer() {
+        let mut printer = CodeBuffer::new(4);
+        printer.println("testing");
+        let actual = printer.to_string();
+
+        assert_eq!("testing", actual);
+    }
+
+    #[test]
+    fn default_should_return_valid_printer() {
+        let mut printer = CodeBuffer::default();
+        printer.println("default");
+        let actual = printer.to_string();
+
+        assert_eq!("default", actual);
+    }
+
+    #[test]
+    fn should_indent_on_indent_right() {
+        // Don't use default as we are relying on the indentation being a
+        // particular length.
+        let mut printer = CodeBuffer::new(4);
+        printer.indent_right();
+        printer.println("testing");
+        let actual = printer.to_string();
+
+        assert_eq!("    testing", actual);
+    }
+
+    #[test]
+    fn spaces_style_should_indent_by_width_per_level() {
+        let mut printer = CodeBuffer::with_style(IndentStyle::Spaces(2));
+        printer.indent_right();
+        printer.indent_right();
+        printer.println("testing");
+        let actual = printer.to_string();
+
+        assert_eq!(b"    testing", actual.as_bytes());
+    }
+
+    #[test]
+    fn tabs_style_should_indent_by_one_tab_per_level() {
+        let mut printer = CodeBuffer::with_style(IndentStyle::Tabs);
+        printer.indent_right();
+        printer.indent_right();
+        printer.println("testing");
+        let actual = printer.to_string();
+
+        assert_eq!(b"\t\ttesting", actual.as_bytes());
+    }
+
+    #[test]
+    fn crlf_should_join_lines_with_carriage_return() {
+        let mut printer = CodeBuffer::new(4);
+        printer.set_line_ending(LineEnding::Crlf);
+        printer.println("first");
+        printer.println_right("second");
+        let actual = printer.to_string();
+
+        assert_eq!(b"first\r\n    second", actual.as_bytes());
+    }
+
+    #[test]
+    fn crlf_should_not_add_separator_to_single_line() {
+        let mut printer = CodeBuffer::new(4);
+        printer.set_line_ending(LineEnding::Crlf);
+        printer.println("testing");
+        let actual = printer.to_string();
+
+        assert_eq!(b"testing", actual.as_bytes());
+    }
+
+    #[test]
+    fn crlf_should_produce_empty_string_for_empty_buffer() {
+        let mut printer = CodeBuffer::new(4);
+        printer.set_line_ending(LineEnding::Crlf);
+        let actual = printer.to_string();
+
+        assert_eq!("", actual);
+    }
+
+    #[test]
+    fn trailing_newline_should_end_output_with_line_ending() {
+        let mut printer = CodeBuffer::new(4);
+        printer.set_trailing_newline(true);
+        printer.println("first");
+        printer.println("second");
+        let actual = printer.to_string();
+
+        assert_eq!("first\nsecond\n", actual);
+        assert_eq!(Some(&b'\n'), actual.as_bytes().last());
+    }
+
+    #[test]
+    fn trailing_newline_should_use_configured_line_ending() {
+        let mut printer = CodeBuffer::new(4);
+        printer.set_trailing_newline(true);
+        printer.set_line_ending(LineEnding::Crlf);
+        printer.println("testing");
+        let actual = printer.to_string();
+
+        assert_eq!("testing\r\n", actual);
+    }
+
+    #[test]
+    fn trailing_newline_should_not_affect_empty_buffer() {
+        let mut printer = CodeBuffer::new(4);
+        printer.set_trailing_newline(true);
+        let actual = printer.to_string();
+
+        assert_eq!("", actual);
+    }
+
+    #[test]
+    fn write_should_continue_line_until_writeln() {
+        let mut printer = CodeBuffer::new(4);
+        printer.indent_right();
+        write!(printer, "let x = ").unwrap();
+        writeln!(printer, "{};", 1).unwrap();
+        let actual = printer.to_string();
+
+        assert_eq!("    let x = 1;", actual);
+    }
+
+    #[test]
+    fn write_should_indent_each_embedded_line() {
+        let mut printer = CodeBuffer::new(4);
+        printer.indent_right();
+        write!(printer, "a\nb\n\nc").unwrap();
+        let actual = printer.to_string();
+
+        assert_eq!("    a\n    b\n\n    c", actual);
+    }
+
+    #[test]
+    fn println_should_end_pending_write() {
+        let mut printer = CodeBuffer::new(4);
+        write!(printer, "first").unwrap();
+        printer.println(" second");
+        printer.println_right("third");
+        let actual = printer.to_string();
+
+        assert_eq!("first second\n    third", actual);
+    }
+
+    #[test]
+    fn print_should_build_single_line_until_println() {
+        let mut printer = CodeBuffer::new(4);
+
+        let expected = "    abc\n    d";
+
+        printer.indent_right();
+        printer.print("a");
+        printer.print("b");
+        printer.println("c");
+        printer.println("d");
+        let actual = printer.to_string();
+
+        assert_eq!(expected, actual);
+    }
+
+    #[test]
+    fn println_fmt_should_end_printed_line() {
+        let mut printer = CodeBuffer::new(4);
+
+        printer.print("let x = ");
+        println_fmt!(printer, "{};", 1);
+        let actual = printer.to_string();
+
+        assert_eq!("let x = 1;", actual);
+    }
+
+    #[test]
+    fn println_fmt_should_chain_like_println() {
+        let mut printer = CodeBuffer::new(4);
+
+        let expected = "fn main() {\n    let x = 1;\n}";
+
+        printer
+            .println("fn main() {")
+            .indent_right()
+            .println_fmt(format_args!("let {} = {};", "x", 1))
+            .println_left("}");
+        let actual = printer.to_string();
+
+        assert_eq!(expected, actual);
+    }
+
+    #[test]
+    fn println_fmt_should_match_println_of_format() {
+        let mut expected = CodeBuffer::new(4);
+        expected.indent_right();
+        expected.println(&format!("let {} = {};", "x", 1));
+
+        let mut actual = CodeBuffer::new(4);
+        actual.indent_right();
+        println_fmt!(actual, "let {} = {};", "x", 1);
+
+        assert_eq!(expected.to_string(), actual.to_string());
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    #[allow(deprecated)]
+    fn indented_printer_alias_should_expose_flush_to_file() {
+        let path = std::env::temp_dir().join("simplegen_indented_printer_alias.txt");
+        let mut printer = crate::IndentedPrinter::new(4);
+        printer.println("fn main() {");
+        printer.println_right("println!(\"Hello, World!\");");
+        printer.println_left("}");
+
+        let mut file = File::create(&path).unwrap();
+        let written = printer.flush_to_file(&mut file).unwrap();
+        let actual = fs::read_to_string(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(printer.to_string(), actual);
+        assert_eq!(actual.len(), written);
+    }
+
+    #[test]
+    fn println_raw_should_not_indent_or_change_level() {
+        let mut printer = CodeBuffer::new(4);
+
+        let expected = "    first\n#raw\n    second";
+
+        printer.indent_right();
+        printer.println("first");
+        printer.println_raw("#raw");
+        printer.println("second");
+        let actual = printer.to_string();
+
+        assert_eq!(expected, actual);
+    }
+
+    #[test]
+    fn println_block_should_indent_each_line() {
+        let mut printer = CodeBuffer::new(4);
+        printer.indent_right();
+        printer.println_block("a\nb\nc");
+        let actual = printer.to_string();
+
+        assert_eq!("    a\n    b\n    c", actual);
+    }
+
+    #[test]
+    fn println_block_should_keep_empty_lines_empty() {
+        let mut printer = CodeBuffer::new(4);
+        printer.indent_right();
+        printer.println_block("a\n\nb");
+        let actual = printer.to_string();
+
+        assert_eq!("    a\n\n    b", actual);
+    }
+
+    #[test]
+    fn blank_line_should_not_be_indented() {
+        let mut printer = CodeBuffer::new(4);
+        printer.indent_right();
+        printer.indent_right();
+        printer.indent_right();
+        printer.blank_line();
+        printer.blank_line();
+        let actual = printer.to_string();
+
+        assert_eq!("\n", actual);
+    }
+
+    #[test]
+    fn trim_trailing_whitespace_should_clean_line_ends() {
+        let mut printer = CodeBuffer::new(4);
+        printer.set_trim_trailing_whitespace(true);
+        printer.indent_right();
+        printer.println("let  x = 1; \t ");
+        printer.println("   ");
+        printer.println("done");
+        let actual = printer.to_string();
+
+        assert_eq!("    let  x = 1;\n\n    done", actual);
+    }
+
+    #[test]
+    fn trailing_whitespace_should_be_kept_by_default() {
+        let mut printer = CodeBuffer::new(4);
+        printer.println("testing  ");
+        let actual = printer.to_string();
+
+        assert_eq!("testing  ", actual);
+    }
+
+    #[test]
+    fn level_should_track_indentation() {
+        let mut printer = CodeBuffer::new(4);
+        printer.indent_right();
+        printer.indent_right();
+        printer.indent_left();
+
+        assert_eq!(1, printer.level());
+    }
+
+    #[test]
+    fn indent_width_should_report_configured_indent() {
+        assert_eq!(2, CodeBuffer::new(2).indent_width());
+        assert_eq!(1, CodeBuffer::with_style(IndentStyle::Tabs).indent_width());
+    }
+
+    #[test]
+    fn set_level_should_jump_to_level() {
+        let mut printer = CodeBuffer::default();
+        printer.set_level(3);
+        printer.println("testing");
+        let actual = printer.to_string();
+
+        assert_eq!("            testing", actual);
+    }
+
+    #[test]
+    fn reset_indent_should_return_to_level_zero() {
+        let mut printer = CodeBuffer::default();
+        printer.set_level(3);
+        printer.reset_indent();
+        printer.println("testing");
+        let actual = printer.to_string();
+
+        assert_eq!("testing", actual);
+    }
+
+    #[test]
+    fn indent_left_by_should_saturate_at_zero() {
+        let mut printer = CodeBuffer::new(4);
+        printer.indent_right_by(3);
+        printer.indent_left_by(5);
+        printer.println("testing");
+        let actual = printer.to_string();
+
+        assert_eq!(0, printer.level());
+        assert_eq!("testing", actual);
+    }
+
+    #[test]
+    fn indent_right_by_should_indent_multiple_levels() {
+        let mut printer = CodeBuffer::new(2);
+        printer.indent_right_by(3);
+        printer.println("testing");
+        let actual = printer.to_string();
+
+        assert_eq!("      testing", actual);
+    }
+
+    #[test]
+    fn lines_should_yield_each_indented_line() {
+        let mut printer = CodeBuffer::new(4);
+        printer.println("a {");
+        printer.println_right("b");
+        printer.println_left("}");
+        let actual: Vec<&str> = printer.lines().collect();
+
+        assert_eq!(3, printer.line_count());
+        assert_eq!(vec!["a {", "    b", "}"], actual);
+    }
+
+    #[test]
+    fn clear_should_empty_buffer_and_keep_settings() {
+        let mut printer = CodeBuffer::new(2);
+        printer.set_line_ending(LineEnding::Crlf);
+        printer.println("a");
+        printer.println_right("b");
+        write!(printer, "c").unwrap();
+
+        printer.clear();
+
+        assert_eq!("", printer.to_string());
+        assert_eq!(0, printer.level());
+
+        printer.println("a");
+        printer.println_right("b");
+        let actual = printer.to_string();
+
+        assert_eq!("a\r\n  b", actual);
+    }
+
+    #[test]
+    fn len_and_is_empty_should_reflect_lines() {
+        let mut printer = CodeBuffer::new(4);
+        assert!(printer.is_empty());
+        assert_eq!(0, printer.len());
+
+        printer.println("testing");
+        assert!(!printer.is_empty());
+        assert_eq!(1, printer.len());
+
+        printer.clear();
+        assert!(printer.is_empty());
+        assert_eq!(0, printer.len());
+    }
+
+    #[test]
+    fn append_should_indent_lines_by_current_level() {
+        let mut section = CodeBuffer::new(4);
+        section.println("a");
+        section.println_right("b");
+
+        let mut printer = CodeBuffer::new(4);
+        printer.set_level(2);
+        printer.append(&section);
+        let actual = printer.to_string();
+
+        assert_eq!("        a\n            b", actual);
+        assert_eq!(2, printer.level());
+    }
+
+    #[test]
+    fn append_raw_should_copy_lines_verbatim() {
+        let mut section = CodeBuffer::new(4);
+        section.println("a");
+        section.println_right("b");
+
+        let mut printer = CodeBuffer::new(4);
+        printer.set_level(2);
+        printer.append_raw(&section);
+        let actual = printer.to_string();
+
+        assert_eq!("a\n    b", actual);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn io_write_should_indent_each_line() {
+        let mut printer = CodeBuffer::new(4);
+        printer.indent_right();
+        io::Write::write_all(&mut printer, b"{\n  \"a\": 1\n}\n").unwrap();
+        let actual = printer.to_string();
+
+        assert_eq!("    {\n      \"a\": 1\n    }", actual);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn io_write_should_join_character_split_across_writes() {
+        let mut printer = CodeBuffer::new(4);
+        let bytes = "caf\u{e9}".as_bytes();
+        io::Write::write_all(&mut printer, &bytes[..4]).unwrap();
+        io::Write::write_all(&mut printer, &bytes[4..]).unwrap();
+        let actual = printer.to_string();
+
+        assert_eq!("caf\u{e9}", actual);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn io_write_should_reject_invalid_utf8() {
+        let mut printer = CodeBuffer::new(4);
+        let error = io::Write::write(&mut printer, &[b'a', 0xff, b'b']).unwrap_err();
+
+        assert_eq!(io::ErrorKind::InvalidData, error.kind());
+        assert_eq!("", printer.to_string());
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn flush_to_writer_should_write_to_string_bytes() {
+        let mut printer = CodeBuffer::new(4);
+        printer.println("a {");
+        printer.println_right("b");
+        printer.println_left("}");
+
+        let mut bytes: Vec<u8> = Vec::new();
+        let written = printer.flush_to_writer(&mut bytes).unwrap();
+
+        assert_eq!(printer.to_string().as_bytes(), bytes.as_slice());
+        assert_eq!(bytes.len(), written);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn write_to_path_should_create_file_with_contents() {
+        let path = std::env::temp_dir().join("simplegen_write_to_path.txt");
+        let mut printer = CodeBuffer::new(4);
+        printer.println("a {");
+        printer.println_right("b");
+        printer.println_left("}");
+
+        let written = printer.write_to_path(&path).unwrap();
+        let actual = fs::read_to_string(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!("a {\n    b\n}", actual);
+        assert_eq!(actual.len(), written);
+    }
+
+    #[test]
+    fn prepend_should_insert_at_front() {
+        let mut printer = CodeBuffer::new(4);
+        printer.println("b");
+        printer.prepend("a");
+        let actual = printer.to_string();
+
+        assert_eq!("a\nb", actual);
+    }
+
+    #[test]
+    fn insert_line_should_insert_indented_line_in_middle() {
+        let mut printer = CodeBuffer::new(4);
+        printer.println("a");
+        printer.println("c");
+        printer.indent_right();
+        printer.insert_line(1, "b");
+        let actual = printer.to_string();
+
+        assert_eq!("a\n    b\nc", actual);
+    }
+
+    #[test]
+    fn insert_line_should_clamp_index_to_end() {
+        let mut printer = CodeBuffer::new(4);
+        printer.println("a");
+        printer.insert_line(10, "b");
+        let actual = printer.to_string();
+
+        assert_eq!("a\nb", actual);
+    }
+
+    #[test]
+    fn replace_line_should_patch_marked_line_at_marked_level() {
+        let mut printer = CodeBuffer::new(4);
+        printer.indent_right();
+        let position = printer.mark();
+        printer.println("placeholder");
+        printer.println_right("body");
+        printer.replace_line(position, "patched");
+        let actual = printer.to_string();
+
+        assert_eq!("    patched\n        body", actual);
+    }
+
+    #[test]
+    #[should_panic(expected = "out of bounds")]
+    fn replace_line_should_panic_past_end_of_buffer() {
+        let mut printer = CodeBuffer::new(4);
+        printer.println("testing");
+        printer.replace_line(1, "testing");
+    }
+
+    #[test]
+    fn clone_should_not_be_affected_by_original() {
+        let mut printer = CodeBuffer::new(4);
+        printer.println("a");
+        printer.indent_right();
+
+        let clone = printer.clone();
+        printer.println("b");
+        printer.set_line_ending(LineEnding::Crlf);
+        printer.indent_right();
+
+        assert_eq!("a", clone.to_string());
+        assert_eq!(1, clone.level());
+    }
+
+    #[test]
+    fn debug_should_show_lines_and_level() {
+        let mut printer = CodeBuffer::new(4);
+        printer.println_right("testing");
+        let actual = format!("{:?}", printer);
+
+        assert!(actual.contains("\"    testing\""));
+        assert!(actual.contains("level: 1"));
+    }
+
+    #[test]
+    fn display_should_match_to_string() {
+        let mut printer = CodeBuffer::new(4);
+        printer.set_trailing_newline(true);
+        printer.println("a {");
+        printer.println_right("b");
+        printer.println_left("}");
+
+        let mut written = String::new();
+        write!(written, "{}", printer).unwrap();
+
+        assert_eq!("a {\n    b\n}\n", format!("{}", printer));
+        assert_eq!(printer.to_string(), written);
+    }
+
+    #[test]
+    fn extend_should_indent_each_line() {
+        let mut printer = CodeBuffer::new(4);
+        printer.indent_right();
+        printer.extend(vec!["a", "b"]);
+        let actual = printer.to_string();
+
+        assert_eq!("    a\n    b", actual);
+    }
+
+    #[test]
+    fn extend_with_empty_iterator_should_do_nothing() {
+        let mut printer = CodeBuffer::new(4);
+        printer.extend(Vec::<String>::new());
+
+        assert!(printer.is_empty());
+    }
+
+    #[test]
+    fn from_lines_should_fill_buffer() {
+        let printer = CodeBuffer::from_lines(vec!["a", "b"]);
+        let actual = printer.to_string();
+
+        assert_eq!("a\nb", actual);
+    }
+
+    #[test]
+    fn println_comment_should_prefix_line() {
+        let mut printer = CodeBuffer::new(4);
+        printer.indent_right();
+        printer.println_comment("testing");
+        let actual = printer.to_string();
+
+        assert_eq!("    // testing", actual);
+    }
+
+    #[test]
+    fn println_comment_should_prefix_each_line_with_configured_prefix() {
+        let mut printer = CodeBuffer::new(4);
+        printer.set_comment_prefix("#");
+        printer.indent_right();
+        printer.println_comment("first\n\nsecond");
+        let actual = printer.to_string();
+
+        assert_eq!("    # first\n    #\n    # second", actual);
+    }
+
+    #[test]
+    fn doc_comment_should_wrap_without_splitting_words() {
+        let mut printer = CodeBuffer::new(4);
+        printer.indent_right();
+        printer.doc_comment(
+            "Generates the lookup table used by the parser at build time.",
+            Some(40),
+        );
+        let actual = printer.to_string();
+
+        let expected =
+            "    /// Generates the lookup table used\n    /// by the parser at build time.";
+        assert_eq!(expected, actual);
+        assert!(printer.lines().all(|line| line.len() <= 40));
+    }
+
+    #[test]
+    fn doc_comment_should_keep_paragraphs_without_wrapping() {
+        let mut printer = CodeBuffer::new(4);
+        printer.doc_comment("Summary.\n\nDetails.", None);
+        let actual = printer.to_string();
+
+        assert_eq!("/// Summary.\n///\n/// Details.", actual);
+    }
+
+    #[test]
+    fn doc_comment_should_write_single_line_for_empty_text() {
+        let mut printer = CodeBuffer::new(4);
+        printer.doc_comment("", Some(40));
+        let actual = printer.to_string();
+
+        assert_eq!("///", actual);
+    }
+
+    #[test]
+    fn max_width_should_wrap_with_indented_continuation() {
+        let mut printer = CodeBuffer::new(4);
+        printer.set_max_width(Some(30));
+        printer.indent_right();
+        printer.println("SELECT id, name, email FROM users WHERE active = 1");
+        let actual = printer.to_string();
+
+        let expected = "    SELECT id, name, email\n        FROM users WHERE\n        active = 1";
+        assert_eq!(expected, actual);
+        assert!(printer.lines().all(|line| line.len() <= 30));
+    }
+
+    #[test]
+    fn max_width_should_put_long_word_on_own_line() {
+        let mut printer = CodeBuffer::new(4);
+        printer.set_max_width(Some(10));
+        printer.println("a abcdefghijkl b");
+        let actual = printer.to_string();
+
+        assert_eq!("a\n    abcdefghijkl\n    b", actual);
+    }
+
+    #[test]
+    fn max_width_should_leave_short_lines_alone() {
+        let mut printer = CodeBuffer::new(4);
+        printer.set_max_width(Some(30));
+        printer.println("let  x = 1;");
+        let actual = printer.to_string();
+
+        assert_eq!("let  x = 1;", actual);
+    }
+
+    #[test]
+    fn println_if_should_skip_false_condition() {
+        let mut printer = CodeBuffer::new(4);
+        printer.println_if(false, "testing");
+        printer.println_right_if(false, "testing");
+        printer.println_left_if(false, "testing");
+
+        assert!(printer.is_empty());
+        assert_eq!(0, printer.level());
+    }
+
+    #[test]
+    fn println_if_should_print_true_condition() {
+        let mut printer = CodeBuffer::new(4);
+        printer.println_if(true, "a");
+        printer.println_right_if(true, "b");
+        printer.println_left_if(true, "c");
+        let actual = printer.to_string();
+
+        assert_eq!("a\n    b\nc", actual);
+    }
+
+    #[test]
+    fn methods_should_chain() {
+        let mut printer = CodeBuffer::new(4);
+
+        let expected = "fn main() {\n    let x = 1;\n    let y = 2;\n}";
+
+        printer
+            .println("fn main() {")
+            .println_right("let x = 1;")
+            .indent_left()
+            .indent_right()
+            .println("let y = 2;")
+            .println_left("}");
+        let actual = printer.to_string();
+
+        assert_eq!(expected, actual);
+    }
+
+    #[test]
+    fn join_with_should_use_custom_separator() {
+        let mut printer = CodeBuffer::new(2);
+        printer.println("a");
+        printer.println_right("b");
+        printer.println_left("c");
+        let actual = printer.join_with(" | ");
+
+        assert_eq!("a |   b | c", actual);
+    }
+
+    #[test]
+    fn join_with_should_return_empty_string_for_empty_buffer() {
+        let printer = CodeBuffer::new(4);
+
+        assert_eq!("", printer.join_with(" | "));
+    }
+
+    #[test]
+    fn at_root_should_restore_level() {
+        let mut printer = CodeBuffer::new(4);
+        printer.set_level(2);
+        printer.at_root(|printer| {
+            printer.println("root");
+        });
+        printer.println("nested");
+        let actual = printer.to_string();
+
+        assert_eq!("root\n        nested", actual);
+        assert_eq!(2, printer.level());
+    }
+
+    #[test]
+    fn at_level_should_restore_level_after_indenting_inside() {
+        let mut printer = CodeBuffer::new(4);
+        printer.at_level(1, |printer| {
+            printer.println("a");
+            printer.println_right("b");
+        });
+        printer.println("c");
+        let actual = printer.to_string();
+
+        assert_eq!("    a\n        b\nc", actual);
+    }
+
+    #[test]
+    fn byte_len_should_match_to_string_len() {
+        let mut printer = CodeBuffer::new(4);
+        assert_eq!(0, printer.byte_len());
+
+        printer.set_line_ending(LineEnding::Crlf);
+        printer.set_trailing_newline(true);
+        printer.println("fn main() {");
+        printer.println_right("println!(\"caf\u{e9}\");");
+        printer.println_left("}");
+
+        assert_eq!(printer.to_string().len(), printer.byte_len());
+    }
+
+    #[test]
+    fn with_capacity_should_reserve_lines() {
+        let mut printer = CodeBuffer::with_capacity(2, 100);
+        assert!(printer.buffer.capacity() >= 100);
+
+        printer.println_right("testing");
+        assert_eq!("  testing", printer.to_string());
+    }
+
+    #[test]
+    fn deep_indentation_should_match_repeated_spaces() {
+        let mut printer = CodeBuffer::new(4);
+        printer.set_level(50);
+        printer.println("testing");
+        let actual = printer.to_string();
+
+        assert_eq!(format!("{}testing", " ".repeat(200)), actual);
+    }
+
+    #[test]
+    fn println_each_should_indent_lines_in_order() {
+        let mut printer = CodeBuffer::new(4);
+        printer.indent_right();
+        printer.println_each(["a", "b", "c"]);
+        let actual = printer.to_string();
+
+        assert_eq!("    a\n    b\n    c", actual);
+    }
+
+    #[test]
+    fn println_str_literal_should_write_escaped_literal() {
+        let mut printer = CodeBuffer::new(4);
+        printer.indent_right();
+        printer.println_str_literal("a\"b", escape_rust_str);
+        let actual = printer.to_string();
+
+        assert_eq!("    \"a\\\"b\"", actual);
+    }
+
+    #[test]
+    fn last_line_mut_should_change_last_line_in_place() {
+        let mut printer = CodeBuffer::new(4);
+        printer.println("a");
+        printer.println_right("b");
+        printer.last_line_mut().unwrap().push(',');
+        let actual = printer.to_string();
+
+        assert_eq!(Some("    b,"), printer.last_line());
+        assert_eq!("a\n    b,", actual);
+    }
+
+    #[test]
+    fn pop_line_should_remove_last_line_and_keep_level() {
+        let mut printer = CodeBuffer::new(4);
+        printer.println("a");
+        printer.println_right("b");
+        let popped = printer.pop_line();
+        let actual = printer.to_string();
+
+        assert_eq!(Some("    b".to_string()), popped);
+        assert_eq!("a", actual);
+        assert_eq!(1, printer.level());
+    }
+
+    #[test]
+    fn pop_line_should_return_none_for_empty_buffer() {
+        let mut printer = CodeBuffer::new(4);
+
+        assert_eq!(None, printer.pop_line());
+    }
+
+    #[test]
+    fn base_level_should_shift_indented_lines() {
+        let mut printer = CodeBuffer::new(4);
+        printer.set_base_level(2);
+        printer.println("a");
+        printer.println_right("b");
+        printer.blank_line();
+        write!(printer, "c").unwrap();
+        let actual = printer.to_string();
+
+        assert_eq!("        a\n            b\n\n            c", actual);
+    }
+
+    #[test]
+    fn base_level_of_zero_should_not_shift_lines() {
+        let mut printer = CodeBuffer::new(4);
+        printer.set_base_level(0);
+        printer.println_right("testing");
+        let actual = printer.to_string();
+
+        assert_eq!("    testing", actual);
+    }
+
+    #[test]
+    fn normalize_should_collapse_blanks_and_trim_whitespace() {
+        let mut printer = CodeBuffer::new(4);
+        printer.println("a  ");
+        printer.blank_line();
+        printer.println_right("   ");
+        printer.blank_line();
+        printer.println("b\t");
+        printer.blank_line();
+        printer.println("c");
+        printer.normalize();
+        let actual = printer.to_string();
+
+        assert_eq!("a\n\n    b\n\n    c", actual);
+    }
+
+    #[test]
+    fn preamble_should_come_before_body_at_column_zero() {
+        let mut printer = CodeBuffer::new(4);
+        printer.println_right("body");
+        printer.set_preamble("// @generated\n// do not edit");
+        let actual = printer.to_string();
+
+        assert_eq!("// @generated\n// do not edit\n    body", actual);
+        assert_eq!(1, printer.len());
+    }
+
+    #[test]
+    fn epilogue_should_come_after_body() {
+        let mut printer = CodeBuffer::new(4);
+        printer.set_trailing_newline(true);
+        printer.set_preamble("start");
+        printer.set_epilogue("end");
+        printer.println("body");
+        let actual = printer.to_string();
+
+        assert_eq!("start\nbody\nend\n", actual);
+        assert_eq!(actual.len(), printer.byte_len());
+    }
+
+    #[test]
+    fn empty_preamble_should_remove_preamble() {
+        let mut printer = CodeBuffer::new(4);
+        printer.set_preamble("start");
+        printer.set_preamble("");
+        printer.println("body");
+        let actual = printer.to_string();
+
+        assert_eq!("body", actual);
+    }
+
+    #[test]
+    fn assert_balanced_should_return_error_for_nonzero_level() {
+        let mut printer = CodeBuffer::new(4);
+        printer.indent_right_by(2);
+
+        assert_eq!(
+            Err(UnbalancedIndentError { level: 2 }),
+            printer.assert_balanced()
+        );
+    }
+
+    #[test]
+    fn assert_balanced_should_succeed_at_level_zero() {
+        let mut printer = CodeBuffer::new(4);
+        printer.println_right("testing");
+        printer.indent_left();
+
+        assert_eq!(Ok(()), printer.assert_balanced());
+    }
+
+    #[test]
+    fn println_continued_should_align_continuations_relative_to_indent() {
+        let mut printer = CodeBuffer::new(4);
+        printer.indent_right();
+        printer.println_continued("call(a,", &["b,", "c);"], 5);
+        let actual = printer.to_string();
+
+        assert_eq!("    call(a,\n         b,\n         c);", actual);
+    }
+
+    #[test]
+    fn from_str_inferring_should_round_trip_nested_code() {
+        let src = "mod a {\n    fn b() {\n        c();\n\n        d(x,\n          y);\n    }\n}";
+        let printer = CodeBuffer::from_str_inferring(src, 4);
+
+        let actual = printer.to_string();
+
+        assert_eq!(src, actual);
+    }
+
+    #[test]
+    fn from_str_inferring_should_continue_at_last_inferred_level() {
+        let mut printer = CodeBuffer::from_str_inferring("fn main() {\n  let x = 1;", 2);
+
+        let expected = "fn main() {\n  let x = 1;\n  let y = 2;\n}";
+
+        printer.println("let y = 2;");
+        printer.println_left("}");
+        let actual = printer.to_string();
+
+        assert_eq!(expected, actual);
+    }
+
+    #[test]
+    fn replace_all_should_replace_token_on_every_line() {
+        let mut printer = CodeBuffer::new(4);
+
+        let expected = "impl Parser {\n    fn new() -> Parser {\n        Parser {}\n    }\n}";
+
+        printer.println("impl __TYPE_NAME__ {");
+        printer.println_right("fn new() -> __TYPE_NAME__ {");
+        printer.println_right("__TYPE_NAME__ {}");
+        printer.println_left("}");
+        printer.println_left("}");
+        printer.replace_all("__TYPE_NAME__", "Parser");
+        let actual = printer.to_string();
+
+        assert_eq!(expected, actual);
+    }
+
+    #[test]
+    fn indent_unit_should_repeat_unit_per_level() {
+        let mut printer = CodeBuffer::with_indent_unit("..|".to_string());
+
+        let expected = "a\n..|b\n..|..|c";
+
+        printer.println("a");
+        printer.println_right("b");
+        printer.println_right("c");
+        let actual = printer.to_string();
+
+        assert_eq!(expected, actual);
+        assert_eq!(3, printer.indent_width());
+    }
+
+    #[test]
+    fn capture_should_not_write_to_parent_buffer() {
+        let mut printer = CodeBuffer::new(4);
+
+        let expected = "    if ready {\n        go();\n    }";
+
+        printer.println("fn main() {");
+        printer.indent_right();
+        let captured = printer.capture(|printer| {
+            printer.println("if ready {");
+            printer.println_right("go();");
+            printer.println_left("}");
+        });
+
+        assert_eq!(expected, captured);
+        assert_eq!("fn main() {", printer.to_string());
+        assert_eq!(1, printer.level());
+    }
+
+    #[test]
+    fn pop_level_should_restore_pushed_level() {
+        let mut printer = CodeBuffer::new(4);
+
+        let expected = "a\n    b\n                c\n    d";
+
+        printer.println("a");
+        printer.println_right("b");
+        printer.push_level();
+        printer.indent_right_by(3);
+        printer.println("c");
+        printer.pop_level();
+        printer.println("d");
+        let actual = printer.to_string();
+
+        assert_eq!(expected, actual);
+        assert_eq!(1, printer.level());
+    }
+
+    #[test]
+    fn pop_level_should_do_nothing_on_empty_stack() {
+        let mut printer = CodeBuffer::new(4);
+
+        printer.indent_right();
+        printer.pop_level();
+
+        assert_eq!(1, printer.level());
+    }
+
+    #[test]
+    fn println_once_should_skip_repeated_import() {
+        let mut printer = CodeBuffer::new(4);
+
+        let expected = "use std::fmt;\nuse std::io;\nuse std::io;";
+
+        printer.println_once("use std::fmt;");
+        printer.println_once("use std::io;");
+        printer.println_once("use std::fmt;");
+        printer.println("use std::io;");
+        let actual = printer.to_string();
+
+        assert_eq!(expected, actual);
+    }
+
+    #[test]
+    fn to_lines_should_match_split_of_to_string() {
+        let mut printer = CodeBuffer::new(4);
+        printer.set_preamble("// Generated");
+        printer.set_trim_trailing_whitespace(true);
+
+        printer.println("fn main() {");
+        printer.println_right("run(); ");
+        printer.println_left("}");
+        let expected: Vec<String> = printer.to_string().split('\n').map(String::from).collect();
+
+        assert_eq!(expected, printer.to_lines());
+        assert_eq!(expected, printer.into_lines());
+    }
+
+    #[test]
+    fn comment_out_range_should_only_change_lines_in_range() {
+        let mut printer = CodeBuffer::new(4);
+
+        let expected = "fn main() {\n    // a();\n    // b();\n}";
+
+        printer.println("fn main() {");
+        printer.println_right("a();");
+        printer.println("b();");
+        printer.println_left("}");
+        printer.comment_out_range(1, 3, "// ");
+        let actual = printer.to_string();
+
+        assert_eq!(expected, actual);
+    }
+
+    #[test]
+    fn uncomment_range_should_reverse_comment_out_range() {
+        let mut printer = CodeBuffer::new(4);
+
+        let expected = "fn main() {\n    a();\n}";
+
+        printer.println("fn main() {");
+        printer.println_right("a();");
+        printer.println_left("}");
+        printer.comment_out_range(0, 10, "// ");
+        printer.uncomment_range(0, 10, "// ");
+        printer.comment_out_range(2, 1, "// ");
+        let actual = printer.to_string();
+
+        assert_eq!(expected, actual);
+    }
+
+    #[test]
+    fn to_string_numbered_should_align_gutter_to_widest_number() {
+        let mut printer = CodeBuffer::new(4);
+
+        for i in 1..=10 {
+            printer.println(&format!("line{}", i));
+        }
+        printer.println_right("line11");
+        let actual = printer.to_string_numbered();
+        let lines: Vec<&str> = actual.split('\n').collect();
+
+        assert_eq!(11, lines.len());
+        assert_eq!(" 1 | line1", lines[0]);
+        assert_eq!(" 9 | line9", lines[8]);
+        assert_eq!("10 | line10", lines[9]);
+        assert_eq!("11 |     line11", lines[10]);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn println_template_should_fill_multi_line_template() {
+        let mut printer = CodeBuffer::new(4);
+        let mut vars = std::collections::HashMap::new();
+        vars.insert("name", "Point");
+        vars.insert("ty", "f64");
+
+        let expected = "    struct Point {\n        x: f64,\n    }";
+
+        printer.indent_right();
+        printer.println_template("struct {name} {\n    x: {ty},\n}", &vars);
+        let actual = printer.to_string();
+
+        assert_eq!(expected, actual);
+    }
+
+    #[test]
+    fn println_indented_block_should_indent_non_blank_lines() {
+        let mut printer = CodeBuffer::new(4);
+
+        let expected = "        let x = 1;\n\n        let y = 2;";
+
+        printer.indent_right_by(2);
+        printer.println_indented_block("let x = 1;\n\nlet y = 2;");
+        let actual = printer.to_string();
+
+        assert_eq!(expected, actual);
+    }
+
+    #[test]
+    fn buffers_built_the_same_way_should_be_equal() {
+        let build = |body: &str| {
+            let mut printer = CodeBuffer::new(4);
+            printer.println("fn main() {");
+            printer.println_right(body);
+            printer.println_left("}");
+            printer
+        };
+
+        assert_eq!(build("run();"), build("run();"));
+        assert_ne!(build("run();"), build("stop();"));
+        assert_ne!(CodeBuffer::new(4), CodeBuffer::new(2));
+    }
+
+    #[test]
+    fn trim_blank_lines_should_only_remove_edges() {
+        let mut printer = CodeBuffer::new(4);
+
+        let expected = "fn a() {}\n\nfn b() {}";
+
+        printer.blank_line();
+        printer.println_right("");
+        printer.println_left("fn a() {}");
+        printer.blank_line();
+        printer.println("fn b() {}");
+        printer.blank_line();
+        printer.println_right("  ");
+        printer.trim_leading_blank_lines();
+        printer.trim_trailing_blank_lines();
+        let actual = printer.to_string();
+
+        assert_eq!(expected, actual);
+    }
+
+    #[test]
+    fn trim_leading_blank_lines_should_move_marks() {
+        let mut printer = CodeBuffer::new(4);
+
+        printer.blank_line();
+        printer.indent_right();
+        let position = printer.mark();
+        printer.println("placeholder");
+        printer.indent_left();
+        printer.trim_leading_blank_lines();
+        printer.replace_line(0, "x");
+        let actual = printer.to_string();
+
+        assert_eq!(1, position);
+        assert_eq!("    x", actual);
+    }
+
+    #[test]
+    fn annotation_should_stack_rust_attributes() {
+        let mut printer = CodeBuffer::new(4);
+
+        let expected = "mod a {\n    #[derive(Debug)]\n    #[derive(Clone)]\n    struct B;\n}";
+
+        printer.println("mod a {");
+        printer.indent_right();
+        printer.annotation("derive(Debug)");
+        printer.annotation("derive(Clone)");
+        printer.println("struct B;");
+        printer.println_left("}");
+        let actual = printer.to_string();
+
+        assert_eq!(expected, actual);
+    }
+
+    #[test]
+    fn annotation_should_use_configured_style() {
+        let mut printer = CodeBuffer::new(4);
+        printer.set_annotation_style("@", "");
+
+        let expected = "    @Override\n    public String toString() {}";
+
+        printer.indent_right();
+        printer.annotation("Override");
+        printer.println("public String toString() {}");
+        let actual = printer.to_string();
+
+        assert_eq!(expected, actual);
+    }
+
+    #[test]
+    fn fork_should_copy_settings_but_not_content() {
+        let mut printer = CodeBuffer::with_style(IndentStyle::Tabs);
+        printer.set_line_ending(LineEnding::Crlf);
+        printer.set_trailing_newline(true);
+        printer.println("first");
+        printer.indent_right();
+
+        let mut forked = printer.fork();
+        forked.println("a");
+        forked.println_right("b");
+        let actual = forked.to_string();
+
+        assert_eq!("a\r\n\tb\r\n", actual);
+        assert_eq!("first\r\n", printer.to_string());
+    }
+
+    #[test]
+    fn deep_indentation_should_be_clamped_without_overflow() {
+        let mut printer = CodeBuffer::new(u32::MAX / 2);
+        printer.set_max_indent_columns(10);
+
+        printer.indent_right_by(u32::MAX);
+        printer.indent_right();
+        printer.println("x");
+        printer.set_base_level(u32::MAX);
+        printer.println("y");
+        let actual = printer.to_string();
+
+        assert_eq!(u32::MAX, printer.level());
+        assert_eq!("x\ny", actual);
+    }
+
+    #[test]
+    fn deep_indentation_should_fill_default_columns() {
+        let mut printer = CodeBuffer::new(4);
+
+        printer.indent_right_by(5000);
+        printer.println("x");
+        let actual = printer.to_string();
+
+        assert_eq!(4096 + 1, actual.len());
+    }
+
+    #[test]
+    #[cfg(feature = "rustfmt")]
+    fn to_formatted_rust_should_fix_spacing() {
+        let rustfmt_installed = std::process::Command::new("rustfmt")
+            .arg("--version")
+            .output()
+            .is_ok();
+        if !rustfmt_installed {
+            return;
+        }
+
+        let mut printer = CodeBuffer::new(4);
+
+        let expected = "fn add(a: u32, b: u32) -> u32 {\n    a + b\n}\n";
+
+        printer.println("fn add(a:u32,b :u32)->u32{");
+        printer.println("a+b");
+        printer.println("}");
+        let actual = printer.to_formatted_rust().unwrap();
+
+        assert_eq!(expected, actual);
+    }
+
+    #[test]
+    #[cfg(feature = "rustfmt")]
+    fn to_formatted_rust_should_return_error_for_invalid_code() {
+        let mut printer = CodeBuffer::new(4);
+
+        printer.println("fn {");
+        let result = printer.to_formatted_rust();
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn max_line_width_should_report_widest_indented_line() {
+        let mut printer = CodeBuffer::new(4);
+
+        printer.println("short");
+        printer.println_right("café_longest");
+        printer.println_left("middle line");
+        let actual = printer.max_line_width();
+
+        assert_eq!(16, actual);
+        assert_eq!(0, CodeBuffer::new(4).max_line_width());
+    }
+
+    #[test]
+    fn banner_should_center_title_between_fill_lines() {
+        let mut printer = CodeBuffer::new(4);
+
+        let expected = "    //==================\n    //      Models\n    //==================";
+
+        printer.indent_right();
+        printer.banner("Models", 20, '=');
+        let actual = printer.to_string();
+
+        assert_eq!(expected, actual);
+    }
+
+    #[test]
+    fn banner_should_truncate_long_title() {
+        let mut printer = CodeBuffer::new(4);
+        printer.set_comment_prefix("#");
+
+        let expected = "#-----\n#Gener\n#-----";
+
+        printer.banner("Generated", 6, '-');
+        let actual = printer.to_string();
+
+        assert_eq!(expected, actual);
+    }
+
+    #[test]
+    fn with_no_indent_should_write_lines_without_prefix() {
+        let mut printer = CodeBuffer::new(4);
+
+        let expected = "            a\n{\n}\n            b";
+
+        printer.indent_right_by(3);
+        printer.println("a");
+        printer.with_no_indent(|printer| {
+            printer.println("{");
+            printer.println("}");
+        });
+        printer.println("b");
+        let actual = printer.to_string();
+
+        assert_eq!(expected, actual);
+    }
+
+    #[test]
+    fn rollback_should_restore_lines_and_level() {
+        let mut printer = CodeBuffer::new(4);
+
+        let expected = "fn main() {\n    done();\n}";
+
+        printer.println("fn main() {");
+        printer.indent_right();
+        let checkpoint = printer.checkpoint();
+        printer.println("if x {");
+        printer.println_right("attempt();");
+        printer.print("unfinished");
+        printer.rollback(checkpoint);
+        printer.println("done();");
+        printer.println_left("}");
+        let actual = printer.to_string();
+
+        assert_eq!(expected, actual);
+    }
+
+    #[test]
+    fn rollback_should_forget_println_once_and_pushed_levels() {
+        let mut printer = CodeBuffer::new(4);
+
+        let expected = "use a;\nuse b;\n    x";
+
+        printer.println_once("use a;");
+        printer.push_level();
+        let checkpoint = printer.checkpoint();
+        printer.println_once("use b;");
+        printer.indent_right();
+        printer.push_level();
+        printer.rollback(checkpoint);
+        printer.println_once("use a;");
+        printer.println_once("use b;");
+        printer.indent_right();
+        printer.pop_level();
+        printer.indent_right();
+        printer.println("x");
+        let actual = printer.to_string();
+
+        assert_eq!(expected, actual);
+    }
+
+    #[test]
+    fn rollback_should_keep_line_started_before_checkpoint() {
+        let mut printer = CodeBuffer::new(4);
+
+        printer.print("let x = ");
+        let checkpoint = printer.checkpoint();
+        printer.print("wrong");
+        printer.println(";");
+        printer.rollback(checkpoint);
+        printer.println("1;");
+        let actual = printer.to_string();
+
+        assert_eq!("let x = 1;", actual);
+    }
+
+    #[test]
+    #[should_panic(expected = "checkpoint at 2 lines is ahead of a buffer of 1 lines")]
+    fn rollback_should_panic_when_rolling_forward() {
+        let mut printer = CodeBuffer::new(4);
+        printer.println("a");
+        printer.println("b");
+        let checkpoint = printer.checkpoint();
+        printer.pop_line();
+
+        printer.rollback(checkpoint);
+    }
+
+    #[test]
+    fn key_value_should_join_with_separator() {
+        let mut printer = CodeBuffer::new(2);
+
+        let expected = "HOST=localhost\nserver:\n  port: 8080";
+
+        printer.key_value("HOST", "localhost", "=");
+        printer.println("server:");
+        printer.indent_right();
+        printer.key_value("port", "8080", ": ");
+        let actual = printer.to_string();
+
+        assert_eq!(expected, actual);
+    }
+
+    #[test]
+    fn key_value_quoted_should_quote_and_escape_value() {
+        let mut printer = CodeBuffer::new(4);
+
+        let expected = r#"path = "C:\\tmp \"x\"""#;
+
+        printer.key_value_quoted("path", r#"C:\tmp "x""#, " = ");
+        let actual = printer.to_string();
+
+        assert_eq!(expected, actual);
+    }
+
+    #[test]
+    fn set_indent_should_only_affect_later_lines() {
+        let mut printer = CodeBuffer::new(2);
+
+        let expected = "a\n  b\n    c";
+
+        printer.println("a");
+        printer.println_right("b");
+        printer.set_indent(4);
+        printer.println("c");
+        let actual = printer.to_string();
+
+        assert_eq!(expected, actual);
+        assert_eq!(4, printer.indent_width());
+    }
+
+    #[test]
+    fn widths_should_count_characters_of_unicode_prefix_and_text() {
+        let mut printer = CodeBuffer::with_indent_unit("│   ".to_string());
+        printer.set_max_width(Some(15));
+
+        let expected = "src\n│   café crème\n│   │   au lait";
+
+        printer.println("src");
+        printer.println_right("café crème au lait");
+        let actual = printer.to_string();
+
+        assert_eq!(expected, actual);
+        assert_eq!(15, printer.max_line_width());
+    }
+
+    #[test]
+    fn into_string_should_match_to_string() {
+        let build = |trailing_newline: bool, lines: &[&str]| {
+            let mut printer = CodeBuffer::new(4);
+            printer.set_line_ending(LineEnding::Crlf);
+            printer.set_trailing_newline(trailing_newline);
+            for line in lines {
+                printer.println_right(line);
+            }
+            printer
+        };
+
+        for trailing_newline in [false, true] {
+            for lines in [&[][..], &["a"][..], &["a", "b", "c"][..]] {
+                let printer = build(trailing_newline, lines);
+                let expected = printer.to_string();
+
+                assert_eq!(expected, printer.into_string());
+            }
+        }
+    }
+
+    #[test]
+    fn into_string_should_include_preamble_and_epilogue() {
+        let mut printer = CodeBuffer::new(4);
+        printer.set_line_ending(LineEnding::Crlf);
+        printer.set_trailing_newline(true);
+        printer.set_preamble("// header\n");
+        printer.set_epilogue("// footer");
+
+        printer.println("fn main() {");
+        printer.println_right("run();");
+        printer.println_left("}");
+        let expected = printer.to_string();
+        let actual = printer.into_string();
+
+        assert_eq!(expected, actual);
+        assert_eq!(
+            "// header\r\n\r\nfn main() {\r\n    run();\r\n}\r\n// footer\r\n",
+            actual
+        );
+    }
+
+    #[test]
+    fn println_empty_should_write_empty_line() {
+        let mut printer = CodeBuffer::new(4);
+
+        let expected = "a\n\n\n        b";
+
+        printer.println("a");
+        printer.indent_right_by(2);
+        printer.println("");
+        printer.println("  \t");
+        printer.println("b");
+        let actual = printer.to_string();
+
+        assert_eq!(expected, actual);
+    }
+
+    #[test]
+    fn indent_blank_lines_should_keep_indentation_of_empty_line() {
+        let mut printer = CodeBuffer::new(4);
+        printer.set_indent_blank_lines(true);
+
+        printer.indent_right_by(2);
+        printer.println("");
+        let actual = printer.to_string();
+
+        assert_eq!("        ", actual);
+    }
+
+    #[test]
+    fn println_expand_tabs_should_convert_leading_tabs_to_spaces() {
+        let mut printer = CodeBuffer::new(4);
+
+        let expected =
+            "    fn main() {\n        if x {\n            y();\t// keep\n        }\n\n    }";
+
+        printer.indent_right();
+        printer.println_expand_tabs(
+            "fn main() {\n\tif x {\n\t\ty();\t// keep\n\t}\n\t\n}",
+            false,
+        );
+        let actual = printer.to_string();
+
+        assert_eq!(expected, actual);
+    }
+
+    #[test]
+    fn println_expand_tabs_should_indent_leading_tabs_in_buffer_style() {
+        let mut printer = CodeBuffer::with_style(IndentStyle::Tabs);
+
+        let expected = "\tfn main() {\n\t\tif x {\n\t\t\ty();\n\t\t}\n\t}";
+
+        printer.indent_right();
+        printer.println_expand_tabs("fn main() {\n\tif x {\n\t\ty();\n\t}\n}", true);
+        let actual = printer.to_string();
+
+        assert_eq!(expected, actual);
+    }
+
+    #[test]
+    fn println_expand_tabs_should_expand_interior_tabs_to_tab_stops_if_asked() {
+        let mut printer = CodeBuffer::new(4);
+
+        let expected = "    a:\tb\n    a:  b\n        ab      c";
+
+        printer.indent_right();
+        printer.println_expand_tabs("a:\tb", false);
+        printer.println_expand_tabs("a:\tb", true);
+        printer.println_expand_tabs("\tab\t\tc", true);
+        let actual = printer.to_string();
+
+        assert_eq!(expected, actual);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn drain_to_writer_should_continue_seamlessly() {
+        let mut printer = CodeBuffer::new(4);
+        let mut all_at_once = CodeBuffer::new(4);
+        all_at_once.set_trailing_newline(true);
+
+        let mut bytes: Vec<u8> = Vec::new();
+
+        for printer in [&mut printer, &mut all_at_once] {
+            printer.println("fn main() {");
+            printer.println_right("a();");
+        }
+        let first = printer.drain_to_writer(&mut bytes).unwrap();
+        for printer in [&mut printer, &mut all_at_once] {
+            printer.println("b();");
+            printer.println_left("}");
+        }
+        let second = printer.drain_to_writer(&mut bytes).unwrap();
+
+        assert_eq!(all_at_once.to_string().as_bytes(), bytes.as_slice());
+        assert_eq!(bytes.len(), first + second);
+        assert!(printer.is_empty());
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn drain_to_writer_should_keep_blank_limit_across_drains() {
+        let mut printer = CodeBuffer::new(4);
+        let mut all_at_once = CodeBuffer::new(4);
+        all_at_once.set_trailing_newline(true);
+
+        let mut bytes: Vec<u8> = Vec::new();
+
+        for printer in [&mut printer, &mut all_at_once] {
+            printer.set_max_consecutive_blanks(Some(1));
+            printer.println("a();");
+            printer.println("");
+        }
+        printer.drain_to_writer(&mut bytes).unwrap();
+        for printer in [&mut printer, &mut all_at_once] {
+            printer.println("");
+        }
+        printer.drain_to_writer(&mut bytes).unwrap();
+        for printer in [&mut printer, &mut all_at_once] {
+            printer.println("");
+            printer.println("b();");
+        }
+        printer.drain_to_writer(&mut bytes).unwrap();
+
+        assert_eq!(all_at_once.to_string().as_bytes(), bytes.as_slice());
+        assert_eq!(b"a();\n\nb();\n", bytes.as_slice());
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn rollback_should_keep_drained_lines() {
+        let mut printer = CodeBuffer::new(4);
+        let mut bytes: Vec<u8> = Vec::new();
+
+        printer.println("a();");
+        let checkpoint = printer.checkpoint();
+        printer.println("b();");
+        printer.drain_to_writer(&mut bytes).unwrap();
+        printer.println("c();");
+        printer.rollback(checkpoint);
+        printer.println("d();");
+        printer.drain_to_writer(&mut bytes).unwrap();
+
+        assert_eq!(b"a();\nb();\nd();\n", bytes.as_slice());
+    }
+
+    #[test]
+    fn element_should_nest_with_escaped_attributes() {
+        let mut printer = CodeBuffer::new(2);
+
+        let expected = "<div class=\"card\" id=\"main\">
+  <span title=\"&quot;quoted&quot;\">
+    Hello
+  </span>
+  <br />
+</div>";
+
+        {
+            let mut div = printer.element("div", &[("class", "card"), ("id", "main")]);
+            {
+                let mut span = div.element("span", &[("title", "\"quoted\"")]);
+                span.println("Hello");
+            }
+            div.void_element("br", &[]);
+        }
+        let actual = printer.to_string();
+
+        assert_eq!(expected, actual);
+    }
+
+    #[test]
+    fn append_eol_comment_should_extend_last_line() {
+        let mut printer = CodeBuffer::new(4);
+        printer.set_comment_prefix("#");
+
+        let expected = "    x = 1 # the answer\n    y = 2";
+
+        printer.indent_right();
+        printer.println("x = 1");
+        let result = printer.append_eol_comment("the answer");
+        printer.println("y = 2");
+        let actual = printer.to_string();
+
+        assert_eq!(Ok(()), result);
+        assert_eq!(expected, actual);
+    }
+
+    #[test]
+    fn append_eol_comment_should_fail_on_empty_buffer() {
+        let mut printer = CodeBuffer::new(4);
+
+        let result = printer.append_eol_comment("orphan");
+
+        assert_eq!(Err(EmptyBufferError), result);
+        assert_eq!("", printer.to_string());
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn serde_should_round_trip_through_json() {
+        let mut printer = CodeBuffer::with_style(IndentStyle::Tabs);
+        printer.set_line_ending(LineEnding::Crlf);
+        printer.println("fn main() {");
+        printer.indent_right();
+        printer.mark();
+        printer.println("run();");
+        printer.print("unfinished");
+
+        let json = serde_json::to_string(&printer).unwrap();
+        let restored: CodeBuffer = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(printer, restored);
+        assert_eq!(1, restored.level());
+    }
+
+    #[test]
+    fn max_consecutive_blanks_should_drop_extra_blank_lines() {
+        let mut printer = CodeBuffer::new(4);
+        printer.set_max_consecutive_blanks(Some(1));
+
+        let expected = "a\n\nb\n\n    c\n";
+
+        printer.println("a");
+        printer.blank_line();
+        printer.println("");
+        printer.println_raw("");
+        printer.println("b");
+        printer.blank_line();
+        printer.indent_right();
+        writeln!(printer).unwrap();
+        writeln!(printer).unwrap();
+        printer.print("");
+        printer.println("");
+        printer.println("c");
+        writeln!(printer).unwrap();
+        writeln!(printer).unwrap();
+        let actual = printer.to_string();
+
+        assert_eq!(expected, actual);
+    }
+
+    #[test]
+    fn max_consecutive_blanks_of_zero_should_drop_every_blank_line() {
+        let mut printer = CodeBuffer::new(4);
+        printer.set_max_consecutive_blanks(Some(0));
+
+        printer.println("a");
+        printer.blank_line();
+        printer.println("b");
+        let actual = printer.to_string();
+
+        assert_eq!("a\nb", actual);
+    }
+
+    #[test]
+    fn println_fields_should_put_fields_at_fixed_columns() {
+        let mut printer = CodeBuffer::new(4);
+
+        printer.indent_right();
+        printer.println_fields(&[("01", 3), ("CUSTOMER-NAME", 8)]);
+        printer.println_fields(&[("5", 3), ("X", 8)]);
+        let actual = printer.to_string();
+        let lines: Vec<&str> = actual.split('\n').collect();
+
+        assert_eq!("    01 CUSTOMER", lines[0]);
+        assert_eq!("    5  X       ", lines[1]);
+        assert_eq!(Some(7), lines[0].find('C'));
+        assert_eq!(Some(7), lines[1].find('X'));
+    }
+
+    #[test]
+    fn println_indexed_should_return_position_for_back_patching() {
+        let mut printer = CodeBuffer::new(4);
+
+        let expected = "loop:\n    jne done\n    dec ecx\ndone:";
+
+        printer.println("loop:");
+        printer.indent_right();
+        let jump = printer.println_indexed("jne ???");
+        printer.println("dec ecx");
+        printer.replace_line(jump, "jne done");
+        printer.indent_left();
+        printer.print("done");
+        let label = printer.println_indexed(":");
+        let actual = printer.to_string();
+
+        assert_eq!(1, jump);
+        assert_eq!(3, label);
+        assert_eq!(expected, actual);
+    }
+
+    #[test]
+    fn block_should_open_brace_on_header_line_by_default() {
+        let mut printer = CodeBuffer::new(4);
+
+        let expected = "if (x) {\n    y();\n}";
+
+        {
+            let mut block = printer.block("if (x)");
+            block.println("y();");
+        }
+        let actual = printer.to_string();
+
+        assert_eq!(expected, actual);
+    }
+
+    #[test]
+    fn allman_block_should_open_brace_on_own_line() {
+        let mut printer = CodeBuffer::new(4);
+        printer.set_brace_style(BraceStyle::Allman);
+
+        let expected = "class A\n{\n    if (x)\n    {\n        y();\n    };\n}";
+
+        {
+            let mut class = printer.block("class A");
+            let mut block = class.block_with_close("if (x)", "};");
+            block.println("y();");
+        }
+        let actual = printer.to_string();
+
+        assert_eq!(expected, actual);
+    }
+
+    #[test]
+    fn with_indented_nonempty_should_leave_buffer_unchanged_if_nothing_written() {
+        let mut printer = CodeBuffer::new(4);
+        printer.println("struct Empty {");
+
+        let written = printer.with_indented_nonempty(|_| {});
+        printer.println("}");
+        let actual = printer.to_string();
+
+        assert!(!written);
+        assert_eq!(0, printer.level());
+        assert_eq!("struct Empty {\n}", actual);
+    }
+
+    #[test]
+    fn with_indented_nonempty_should_indent_lines_written() {
+        let mut printer = CodeBuffer::new(4);
+        printer.println("struct Point {");
+
+        let written = printer.with_indented_nonempty(|printer| {
+            printer.println("x: u32,");
+        });
+        printer.println("}");
+        let actual = printer.to_string();
+
+        assert!(written);
+        assert_eq!(0, printer.level());
+        assert_eq!("struct Point {\n    x: u32,\n}", actual);
+    }
+
+    #[test]
+    fn chunks_should_split_output_by_line_count() {
+        let mut printer = CodeBuffer::new(4);
+
+        printer.indent_right();
+        for number in 0..10 {
+            printer.println(&number.to_string());
+        }
+        let actual = printer.chunks(4);
+
+        assert_eq!(
+            vec![
+                "    0\n    1\n    2\n    3",
+                "    4\n    5\n    6\n    7",
+                "    8\n    9",
+            ],
+            actual
+        );
+    }
+
+    #[test]
+    fn chunks_of_zero_lines_should_return_whole_output() {
+        let mut printer = CodeBuffer::new(4);
+        printer.set_trailing_newline(true);
+
+        printer.println("a");
+        printer.println("b");
+        let actual = printer.chunks(0);
+
+        assert_eq!(vec![printer.to_string()], actual);
+        assert!(CodeBuffer::new(4).chunks(0).is_empty());
+    }
+
+    #[test]
+    fn wrap_in_block_comment_should_write_tokens_at_column_zero() {
+        let mut printer = CodeBuffer::new(4);
+
+        let expected = "/*\n    let x = 1;\n    let y = 2;\n*/";
+
+        printer.indent_right();
+        printer.println("let x = 1;");
+        printer.println("let y = 2;");
+        printer.wrap_in_block_comment("/*", "*/");
+        let actual = printer.to_string();
+
+        assert_eq!(expected, actual);
+    }
+
+    #[test]
+    fn wrap_in_block_comment_should_prefix_interior_lines() {
+        let mut printer = CodeBuffer::new(4);
+        printer.set_block_comment_line_prefix(" * ");
+
+        let expected = "/**\n * fn main() {\n *\n *     run();\n * }\n */";
+
+        printer.println("fn main() {");
+        printer.blank_line();
+        printer.println_right("run();");
+        printer.println_left("}");
+        printer.wrap_in_block_comment("/**", " */");
+        let actual = printer.to_string();
+
+        assert_eq!(expected, actual);
+    }
+
+    #[test]
+    fn render_template_should_write_if_block_at_current_level() {
+        let mut printer = CodeBuffer::new(4);
+        let mut ctx = TemplateContext::new();
+        ctx.set("name", "run").set("is_pub", "yes");
+
+        let template = "{{#if is_pub}}\npub fn {name}() {}\n{{/if}}\n{{#if is_async}}\nasync fn {name}() {}\n{{/if}}\n";
+        printer.indent_right();
+        printer.render_template(template, &ctx);
+        let actual = printer.to_string();
+
+        assert_eq!("    pub fn run() {}", actual);
+    }
+
+    #[test]
+    fn render_template_should_repeat_each_loop_per_item() {
+        let mut printer = CodeBuffer::new(4);
+        let mut ctx = TemplateContext::new();
+        ctx.set("enum", "Op").set_list("ops", ["Add", "Sub"]);
+
+        let expected = "match op {\n    Op::Add => add(),\n    Op::Sub => sub(),\n}";
+
+        let template = "match op {\n{{#each ops}}\n    {enum}::{this} => {this}(),\n{{/each}}\n}";
+        printer.render_template(template, &ctx);
+        printer.replace_all("Add()", "add()");
+        printer.replace_all("Sub()", "sub()");
+        let actual = printer.to_string();
+
+        assert_eq!(expected, actual);
+    }
+
+    #[test]
+    fn render_template_should_write_nothing_for_empty_output() {
+        let mut printer = CodeBuffer::new(4);
+
+        printer.render_template("{{#each missing}}\nx\n{{/each}}\n", &TemplateContext::new());
+
+        assert_eq!(0, printer.line_count());
+    }
+
+    #[test]
+    #[should_panic(expected = "println was given a string containing a newline")]
+    fn strict_single_line_should_panic_on_embedded_newline() {
+        let mut printer = CodeBuffer::new(4);
+        printer.set_strict_single_line(true);
+
+        printer.println("let x = 1;");
+        printer.println("let y = 2;\nlet z = 3;");
+    }
+
+    #[test]
+    #[should_panic(expected = "println was given a string containing a newline")]
+    fn strict_single_line_should_panic_on_newline_in_println_fmt() {
+        let mut printer = CodeBuffer::new(4);
+        printer.set_strict_single_line(true);
+
+        println_fmt!(printer, "let {} = 1;\nlet z = 3;", "y");
+    }
+
+    #[test]
+    fn println_should_store_embedded_newline_as_one_line_by_default() {
+        let mut printer = CodeBuffer::new(4);
+
+        printer.indent_right();
+        printer.println("let y = 2;\nlet z = 3;");
+        let actual = printer.to_string();
+
+        assert_eq!(1, printer.line_count());
+        assert_eq!("    let y = 2;\nlet z = 3;", actual);
+    }
+
+    #[test]
+    fn diff_should_mark_inserted_line_as_added() {
+        let mut printer = CodeBuffer::new(4);
+        let mut other = CodeBuffer::new(4);
+
+        for buffer in [&mut printer, &mut other] {
+            buffer.println("fn main() {");
+            buffer.indent_right();
+        }
+        other.println("init();");
+        for buffer in [&mut printer, &mut other] {
+            buffer.println("run();");
+            buffer.println_left("}");
+        }
+        let actual = printer.diff(&other);
+
+        assert_eq!(
+            vec![
+                DiffLine::Same("fn main() {".to_string()),
+                DiffLine::Added("    init();".to_string()),
+                DiffLine::Same("    run();".to_string()),
+                DiffLine::Same("}".to_string()),
+            ],
+            actual
+        );
+        assert_eq!(
+            " fn main() {\n+    init();\n     run();\n }",
+            printer.diff_string(&other)
+        );
+    }
+
+    #[test]
+    fn diff_of_identical_buffers_should_only_have_same_lines() {
+        let mut printer = CodeBuffer::new(4);
+        printer.println("a");
+        printer.println("b");
+
+        let actual = printer.diff(&printer.clone());
+
+        assert_eq!(
+            vec![
+                DiffLine::Same("a".to_string()),
+                DiffLine::Same("b".to_string())
+            ],
+            actual
+        );
+    }
+
+    #[test]
+    fn append_should_not_join_pending_line_or_rewrap_lines() {
+        let mut printer = CodeBuffer::new(4);
+        printer.set_max_width(Some(16));
+        let mut other = CodeBuffer::new(4);
+        other.println("if x {");
+        other.println_right("call(one, two, three);");
+        other.println_left("}");
+
+        let expected = "    // body\n    if x {\n        call(one, two, three);\n    }";
+
+        printer.indent_right();
+        printer.print("// body");
+        printer.append(&other);
+        let actual = printer.to_string();
+
+        assert_eq!(expected, actual);
+    }
+
+    #[test]
+    fn empty_indent_unit_should_not_slow_down_deep_levels() {
+        let mut printer = CodeBuffer::with_indent_unit(String::new());
+
+        printer.set_level(u32::MAX);
+        printer.println("deep");
+        let actual = printer.to_string();
+
+        assert_eq!("deep", actual);
+    }
+
+    #[test]
+    fn replace_all_should_not_change_non_whitespace_indent_units() {
+        let mut printer = CodeBuffer::with_indent_unit("..|".to_string());
+
+        let expected = "root\n..|..|leaf:child";
+
+        printer.println("root");
+        printer.indent_right();
+        printer.println_right("leaf|child");
+        printer.replace_all("|", ":");
+        let actual = printer.to_string();
+
+        assert_eq!(expected, actual);
+    }
+
+    #[test]
+    fn comment_out_range_should_comment_after_non_whitespace_indent_units() {
+        let mut printer = CodeBuffer::with_indent_unit("| ".to_string());
+
+        printer.indent_right();
+        printer.println("x");
+        printer.comment_out_range(0, 1, "# ");
+        printer.uncomment_range(0, 1, "# ");
+        printer.comment_out_range(0, 1, "; ");
+        let actual = printer.to_string();
+
+        assert_eq!("| ; x", actual);
+    }
+
+    #[test]
+    fn println_continued_should_keep_alignment_past_max_width() {
+        let mut printer = CodeBuffer::new(4);
+        printer.set_max_width(Some(20));
+
+        let expected = "    fn f(a: u8,\n         bb: SomeLongType,\n         c: u8);";
+
+        printer.indent_right();
+        printer.println_continued("fn f(a: u8,", &["bb: SomeLongType,", "c: u8);"], 5);
+        let actual = printer.to_string();
+
+        assert_eq!(expected, actual);
+    }
+
+    #[test]
+    fn normalize_should_move_marks_with_their_lines() {
+        let mut printer = CodeBuffer::new(4);
+
+        let expected = "fn main() {
+
+    let x = 2;
+}";
+
+        printer.println("fn main() {");
+        printer.indent_right();
+        printer.blank_line();
+        printer.blank_line();
+        printer.mark();
+        printer.blank_line();
+        printer.mark();
+        printer.println("let x = 1;");
+        printer.println_left("}");
+        printer.normalize();
+        printer.replace_line(2, "let x = 2;");
+        let actual = printer.to_string();
+
+        assert_eq!(expected, actual);
+        assert_eq!(vec![(2, 1)], printer.marks.into_iter().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn prepend_should_move_marks_down() {
+        let mut printer = CodeBuffer::new(4);
+
+        let expected = "use std::fmt;
+fn main() {
+    let x = 2;
+}";
+
+        printer.println("fn main() {");
+        printer.indent_right();
+        let position = printer.mark();
+        printer.println("let x = 1;");
+        printer.println_left("}");
+        printer.prepend("use std::fmt;");
+        printer.replace_line(position + 1, "let x = 2;");
+        let actual = printer.to_string();
+
+        assert_eq!(expected, actual);
     }
 
     #[test]
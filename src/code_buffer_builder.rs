@@ -0,0 +1,128 @@
+use crate::{CodeBuffer, IndentStyle, LineEnding};
+
+/// A [CodeBufferBuilder] configures a [CodeBuffer] before it is created.
+///
+/// The defaults of the builder are the same as [CodeBuffer::default()].
+///
+/// # Example
+/// ```
+/// use simplegen::{CodeBufferBuilder, LineEnding};
+///
+/// let mut buffer = CodeBufferBuilder::new()
+///     .indent(2)
+///     .line_ending(LineEnding::Crlf)
+///     .trailing_newline(true)
+///     .build();
+///
+/// buffer.println("{");
+/// buffer.println_right("\"number\": 1");
+/// buffer.println_left("}");
+///
+/// assert_eq!("{\r\n  \"number\": 1\r\n}\r\n", buffer.to_string());
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct CodeBufferBuilder {
+    /// Whitespace to indent code by for each level.
+    style: IndentStyle,
+    /// Separator placed between lines.
+    line_ending: LineEnding,
+    /// Whether the last line is followed by a line ending.
+    trailing_newline: bool,
+}
+
+impl CodeBufferBuilder {
+    /// Create a new `CodeBufferBuilder` with the default settings.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use simplegen::CodeBufferBuilder;
+    ///
+    /// let buffer = CodeBufferBuilder::new().build();
+    /// ```
+    pub fn new() -> Self {
+        CodeBufferBuilder::default()
+    }
+
+    /// Indent by the given number of spaces for each level.
+    ///
+    /// # Arguments
+    ///
+    /// * `indent` - Number of spaces to indent by.
+    pub fn indent(self, indent: u32) -> Self {
+        self.style(IndentStyle::Spaces(indent))
+    }
+
+    /// Indent by a single tab character for each level.
+    pub fn tabs(self) -> Self {
+        self.style(IndentStyle::Tabs)
+    }
+
+    /// Indent with the given [IndentStyle].
+    ///
+    /// # Arguments
+    ///
+    /// * `style` - Whitespace to indent by for each level.
+    pub fn style(mut self, style: IndentStyle) -> Self {
+        self.style = style;
+        self
+    }
+
+    /// Separate lines with the given [LineEnding].
+    ///
+    /// # Arguments
+    ///
+    /// * `line_ending` - Line ending to join lines with.
+    pub fn line_ending(mut self, line_ending: LineEnding) -> Self {
+        self.line_ending = line_ending;
+        self
+    }
+
+    /// Set whether the last line is followed by a line ending.
+    ///
+    /// # Arguments
+    ///
+    /// * `trailing_newline` - Whether to end the last line with a line ending.
+    pub fn trailing_newline(mut self, trailing_newline: bool) -> Self {
+        self.trailing_newline = trailing_newline;
+        self
+    }
+
+    /// Create the configured [CodeBuffer].
+    pub fn build(self) -> CodeBuffer {
+        let mut buffer = CodeBuffer::with_style(self.style);
+        buffer.set_line_ending(self.line_ending);
+        buffer.set_trailing_newline(self.trailing_newline);
+        buffer
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::code_buffer_builder::CodeBufferBuilder;
+    use crate::LineEnding;
+
+    #[test]
+    fn build_should_apply_each_setting() {
+        let mut printer = CodeBufferBuilder::new()
+            .tabs()
+            .line_ending(LineEnding::Crlf)
+            .trailing_newline(true)
+            .build();
+        printer.println("a");
+        printer.println_right("b");
+        let actual = printer.to_string();
+
+        assert_eq!("a\r\n\tb\r\n", actual);
+    }
+
+    #[test]
+    fn defaults_should_match_default_buffer() {
+        let mut printer = CodeBufferBuilder::new().build();
+        printer.println("a");
+        printer.println_right("b");
+        let actual = printer.to_string();
+
+        assert_eq!("a\n    b", actual);
+    }
+}
@@ -0,0 +1,94 @@
+/// A [CodeSink] is anything that indented lines of code can be written to.
+///
+/// It's implemented by [CodeBuffer](crate::CodeBuffer), which keeps lines in
+/// memory, and by [StreamingPrinter](crate::StreamingPrinter), which writes
+/// them straight to an [io::Write](std::io::Write). Generation code that
+/// takes a `&mut impl CodeSink` can be used with either.
+///
+/// # Example
+/// ```
+/// use simplegen::{CodeBuffer, CodeSink};
+///
+/// fn write_main(sink: &mut impl CodeSink) {
+///     sink.println("fn main() {");
+///     sink.println_right("run();");
+///     sink.println_left("}");
+/// }
+///
+/// let mut buffer = CodeBuffer::default();
+/// write_main(&mut buffer);
+///
+/// assert_eq!("fn main() {\n    run();\n}", buffer.to_string());
+/// ```
+pub trait CodeSink {
+    /// Write a line at the current indentation level.
+    ///
+    /// # Arguments
+    ///
+    /// * `str` - String to write.
+    fn println(&mut self, str: &str) -> &mut Self;
+
+    /// Indent right.
+    fn indent_right(&mut self) -> &mut Self;
+
+    /// Indent left. The indentation level never goes below 0.
+    fn indent_left(&mut self) -> &mut Self;
+
+    /// Indent right then write a line.
+    ///
+    /// # Arguments
+    ///
+    /// * `str` - String to write.
+    fn println_right(&mut self, str: &str) -> &mut Self {
+        self.indent_right().println(str)
+    }
+
+    /// Indent left then write a line.
+    ///
+    /// # Arguments
+    ///
+    /// * `str` - String to write.
+    fn println_left(&mut self, str: &str) -> &mut Self {
+        self.indent_left().println(str)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{CodeBuffer, CodeSink};
+
+    fn write_function(sink: &mut impl CodeSink, name: &str) {
+        sink.println(&format!("fn {}() {{", name));
+        sink.println_right("if ready {");
+        sink.println_right("go();");
+        sink.println_left("}");
+        sink.println_left("}");
+    }
+
+    #[test]
+    fn generic_function_should_write_to_code_buffer() {
+        let mut printer = CodeBuffer::new(4);
+
+        let expected = "fn run() {\n    if ready {\n        go();\n    }\n}";
+
+        write_function(&mut printer, "run");
+        let actual = printer.to_string();
+
+        assert_eq!(expected, actual);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn streaming_printer_should_match_code_buffer() {
+        let mut buffer = CodeBuffer::new(4);
+        let mut bytes: Vec<u8> = Vec::new();
+        let mut printer = crate::StreamingPrinter::new(&mut bytes, 4);
+
+        write_function(&mut buffer, "run");
+        write_function(&mut printer, "run");
+        printer.flush().unwrap();
+        drop(printer);
+
+        assert_eq!(buffer.to_string().as_bytes(), bytes.as_slice());
+    }
+}
@@ -0,0 +1,84 @@
+use alloc::format;
+
+use crate::CodeBuffer;
+
+/// A [CounterList] writes numbered list items to a [CodeBuffer], counting up
+/// from a starting number.
+///
+/// It's created by calling [CodeBuffer::numbered_list()]. Each item is
+/// written as `{n}. {text}` at the buffer's current indentation level.
+///
+/// # Example
+/// ```
+/// use simplegen::CodeBuffer;
+///
+/// let mut buffer = CodeBuffer::default();
+///
+/// let mut list = buffer.numbered_list(1);
+/// for step in &["Install", "Configure"] {
+///     list.item(step);
+/// }
+///
+/// assert_eq!("1. Install\n2. Configure", buffer.to_string());
+/// ```
+pub struct CounterList<'a> {
+    /// The buffer that items are written to.
+    buffer: &'a mut CodeBuffer,
+    /// Number of the next item.
+    next: usize,
+}
+
+impl<'a> CounterList<'a> {
+    /// Create a list that writes items to `buffer`, starting at `start`.
+    pub(crate) fn new(buffer: &'a mut CodeBuffer, start: usize) -> Self {
+        CounterList {
+            buffer,
+            next: start,
+        }
+    }
+
+    /// Write an item with the next number.
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - Text of the item.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use simplegen::CodeBuffer;
+    ///
+    /// let mut buffer = CodeBuffer::default();
+    /// buffer.numbered_list(9).item("nine").item("ten");
+    ///
+    /// assert_eq!("9. nine\n10. ten", buffer.to_string());
+    /// ```
+    pub fn item(&mut self, text: &str) -> &mut Self {
+        self.buffer.println(&format!("{}. {}", self.next, text));
+        self.next += 1;
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::CodeBuffer;
+
+    #[test]
+    fn should_number_items_from_start() {
+        let mut printer = CodeBuffer::new(4);
+
+        let expected = "Steps:\n    1. Parse\n    2. Check\n    3. Emit";
+
+        printer.println("Steps:");
+        printer.indent_right();
+        printer
+            .numbered_list(1)
+            .item("Parse")
+            .item("Check")
+            .item("Emit");
+        let actual = printer.to_string();
+
+        assert_eq!(expected, actual);
+    }
+}
@@ -0,0 +1,128 @@
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+
+/// A line in the diff between the output of two
+/// [CodeBuffer](crate::CodeBuffer)s, returned by
+/// [CodeBuffer::diff()](crate::CodeBuffer::diff).
+///
+/// # Example
+/// ```
+/// use simplegen::{CodeBuffer, DiffLine};
+///
+/// let mut old = CodeBuffer::default();
+/// old.println("let x = 1;");
+/// let mut new = CodeBuffer::default();
+/// new.println("let x = 2;");
+///
+/// assert_eq!(
+///     vec![
+///         DiffLine::Removed("let x = 1;".to_string()),
+///         DiffLine::Added("let x = 2;".to_string()),
+///     ],
+///     old.diff(&new)
+/// );
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DiffLine {
+    /// A line that is in the output of both buffers.
+    Same(String),
+    /// A line that is only in the output of the other buffer.
+    Added(String),
+    /// A line that is only in the output of this buffer.
+    Removed(String),
+}
+
+/// Diff two lists of lines using their longest common subsequence. Lines at
+/// the start and end that are the same in both are matched up first, so only
+/// the lines in between need a table. When a line is changed, the removed
+/// line comes before the added one.
+pub(crate) fn diff_lines(old: &[&str], new: &[&str]) -> Vec<DiffLine> {
+    let prefix = old.iter().zip(new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let old_middle = &old[prefix..old.len() - suffix];
+    let new_middle = &new[prefix..new.len() - suffix];
+
+    // lengths[i * width + j] is the length of the longest common subsequence
+    // of old_middle[i..] and new_middle[j..].
+    let width = new_middle.len() + 1;
+    let mut lengths = vec![0; (old_middle.len() + 1) * width];
+    for i in (0..old_middle.len()).rev() {
+        for j in (0..new_middle.len()).rev() {
+            lengths[i * width + j] = if old_middle[i] == new_middle[j] {
+                lengths[(i + 1) * width + j + 1] + 1
+            } else {
+                lengths[(i + 1) * width + j].max(lengths[i * width + j + 1])
+            };
+        }
+    }
+
+    let same = |line: &&str| DiffLine::Same(line.to_string());
+    let mut diff: Vec<DiffLine> = old[..prefix].iter().map(same).collect();
+    let (mut i, mut j) = (0, 0);
+    while i < old_middle.len() && j < new_middle.len() {
+        if old_middle[i] == new_middle[j] {
+            diff.push(DiffLine::Same(old_middle[i].to_string()));
+            i += 1;
+            j += 1;
+        } else if lengths[(i + 1) * width + j] >= lengths[i * width + j + 1] {
+            diff.push(DiffLine::Removed(old_middle[i].to_string()));
+            i += 1;
+        } else {
+            diff.push(DiffLine::Added(new_middle[j].to_string()));
+            j += 1;
+        }
+    }
+    diff.extend(
+        old_middle[i..]
+            .iter()
+            .map(|line| DiffLine::Removed(line.to_string())),
+    );
+    diff.extend(
+        new_middle[j..]
+            .iter()
+            .map(|line| DiffLine::Added(line.to_string())),
+    );
+    diff.extend(old[old.len() - suffix..].iter().map(same));
+    diff
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::diff::{diff_lines, DiffLine};
+
+    #[test]
+    fn should_find_longest_common_subsequence() {
+        let actual = diff_lines(&["a", "b", "c", "d"], &["b", "x", "d", "e"]);
+
+        assert_eq!(
+            vec![
+                DiffLine::Removed("a".to_string()),
+                DiffLine::Same("b".to_string()),
+                DiffLine::Removed("c".to_string()),
+                DiffLine::Added("x".to_string()),
+                DiffLine::Same("d".to_string()),
+                DiffLine::Added("e".to_string()),
+            ],
+            actual
+        );
+    }
+
+    #[test]
+    fn should_handle_empty_sides() {
+        assert_eq!(
+            vec![DiffLine::Added("a".to_string())],
+            diff_lines(&[], &["a"])
+        );
+        assert_eq!(
+            vec![DiffLine::Removed("a".to_string())],
+            diff_lines(&["a"], &[])
+        );
+        assert!(diff_lines(&[], &[]).is_empty());
+    }
+}
@@ -0,0 +1,37 @@
+use core::fmt;
+
+/// Error returned by [CodeBuffer::assert_balanced()](crate::CodeBuffer::assert_balanced)
+/// when the indentation level hasn't returned to 0.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UnbalancedIndentError {
+    /// Indentation level the buffer was left at.
+    pub level: u32,
+}
+
+impl fmt::Display for UnbalancedIndentError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "indentation is unbalanced, {} level(s) were never indented left",
+            self.level
+        )
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for UnbalancedIndentError {}
+
+/// Error returned by
+/// [CodeBuffer::append_eol_comment()](crate::CodeBuffer::append_eol_comment)
+/// when there is no line to append the comment to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EmptyBufferError;
+
+impl fmt::Display for EmptyBufferError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "the buffer has no lines")
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for EmptyBufferError {}
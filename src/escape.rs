@@ -0,0 +1,165 @@
+//! Functions that turn strings into escaped, quoted string literals, for
+//! embedding text in generated code.
+
+use alloc::string::String;
+use core::fmt::Write;
+
+/// Escape a string as a Rust string literal, wrapped in double quotes.
+///
+/// Quotes, backslashes, tabs, newlines, carriage returns and other control
+/// characters are escaped. Non-ASCII characters are kept as they are, as Rust
+/// source is UTF-8.
+///
+/// # Arguments
+///
+/// * `str` - String to escape.
+///
+/// # Examples
+///
+/// ```
+/// use simplegen::escape::escape_rust_str;
+///
+/// assert_eq!(r#""say \"hi\"\n""#, escape_rust_str("say \"hi\"\n"));
+/// ```
+pub fn escape_rust_str(str: &str) -> String {
+    let mut escaped = String::with_capacity(str.len() + 2);
+    escaped.push('"');
+    for c in str.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            '\0' => escaped.push_str("\\0"),
+            c if c.is_control() => write!(escaped, "\\u{{{:x}}}", c as u32).unwrap(),
+            c => escaped.push(c),
+        }
+    }
+    escaped.push('"');
+    escaped
+}
+
+/// Escape a string as a JSON string, wrapped in double quotes.
+///
+/// Quotes, backslashes and control characters are escaped, and non-ASCII
+/// characters are written as `\u` escapes, so the output is plain ASCII.
+/// Characters outside the Basic Multilingual Plane are written as UTF-16
+/// surrogate pairs.
+///
+/// # Arguments
+///
+/// * `str` - String to escape.
+///
+/// # Examples
+///
+/// ```
+/// use simplegen::escape::escape_json_str;
+///
+/// assert_eq!(r#""caf\u00e9\t\"x\"""#, escape_json_str("café\t\"x\""));
+/// ```
+pub fn escape_json_str(str: &str) -> String {
+    let mut escaped = String::with_capacity(str.len() + 2);
+    escaped.push('"');
+    for c in str.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            '\u{8}' => escaped.push_str("\\b"),
+            '\u{c}' => escaped.push_str("\\f"),
+            c if c.is_ascii() && !c.is_ascii_control() => escaped.push(c),
+            c => {
+                let mut units = [0; 2];
+                for unit in c.encode_utf16(&mut units) {
+                    write!(escaped, "\\u{:04x}", unit).unwrap();
+                }
+            }
+        }
+    }
+    escaped.push('"');
+    escaped
+}
+
+/// Escape a string as an XML or HTML attribute value, wrapped in double
+/// quotes.
+///
+/// Ampersands, angle brackets, and single and double quotes are replaced by
+/// character references. Other characters are kept as they are.
+///
+/// # Arguments
+///
+/// * `str` - String to escape.
+///
+/// # Examples
+///
+/// ```
+/// use simplegen::escape::escape_xml_attr;
+///
+/// assert_eq!(r#""a &amp; &quot;b&quot;""#, escape_xml_attr("a & \"b\""));
+/// ```
+pub fn escape_xml_attr(str: &str) -> String {
+    let mut escaped = String::with_capacity(str.len() + 2);
+    escaped.push('"');
+    for c in str.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped.push('"');
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::escape::{escape_json_str, escape_rust_str, escape_xml_attr};
+
+    #[test]
+    fn rust_should_escape_quotes_and_backslashes() {
+        let actual = escape_rust_str(r#"C:\path "quoted""#);
+
+        assert_eq!(r#""C:\\path \"quoted\"""#, actual);
+    }
+
+    #[test]
+    fn rust_should_escape_whitespace_and_control_characters() {
+        let actual = escape_rust_str("a\tb\nc\r\0\u{7}");
+
+        assert_eq!(r#""a\tb\nc\r\0\u{7}""#, actual);
+    }
+
+    #[test]
+    fn rust_should_keep_non_ascii() {
+        let actual = escape_rust_str("café ☃");
+
+        assert_eq!("\"café ☃\"", actual);
+    }
+
+    #[test]
+    fn json_should_escape_quotes_backslashes_and_tabs() {
+        let actual = escape_json_str("\\\"\t\u{8}\u{c}\u{1}");
+
+        assert_eq!(r#""\\\"\t\b\f\u0001""#, actual);
+    }
+
+    #[test]
+    fn json_should_escape_non_ascii() {
+        let actual = escape_json_str("é😀");
+
+        assert_eq!(r#""\u00e9\ud83d\ude00""#, actual);
+    }
+
+    #[test]
+    fn xml_attr_should_escape_markup_characters() {
+        let actual = escape_xml_attr("<a href='x'>&\"é");
+
+        assert_eq!(r#""&lt;a href=&#39;x&#39;&gt;&amp;&quot;é""#, actual);
+    }
+}
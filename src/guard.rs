@@ -0,0 +1,417 @@
+use alloc::format;
+use alloc::string::{String, ToString};
+use core::ops::{Deref, DerefMut};
+
+use crate::CodeBuffer;
+
+/// An [IndentGuard] indents a [CodeBuffer] right for as long as it is alive.
+///
+/// It's created by calling [CodeBuffer::indented()]. The indentation level is
+/// returned to where it was when the guard is dropped, so you can't forget to
+/// call [CodeBuffer::indent_left()]. The guard dereferences to the
+/// [CodeBuffer] it borrows, so you write lines through the guard itself.
+///
+/// # Example
+/// ```
+/// use simplegen::CodeBuffer;
+///
+/// let mut buffer = CodeBuffer::new(4);
+///
+/// buffer.println("fn add_one(x: u64) -> u64 {");
+/// {
+///     let mut body = buffer.indented();
+///     body.println("x + 1");
+/// }
+/// buffer.println("}");
+///
+/// assert_eq!("fn add_one(x: u64) -> u64 {\n    x + 1\n}", buffer.to_string());
+/// ```
+pub struct IndentGuard<'a> {
+    /// The buffer that was indented when the guard was created.
+    buffer: &'a mut CodeBuffer,
+}
+
+impl<'a> IndentGuard<'a> {
+    /// Indent the buffer right and return a guard that indents it left again
+    /// on drop.
+    pub(crate) fn new(buffer: &'a mut CodeBuffer) -> Self {
+        buffer.indent_right();
+        IndentGuard { buffer }
+    }
+}
+
+impl Deref for IndentGuard<'_> {
+    type Target = CodeBuffer;
+
+    fn deref(&self) -> &Self::Target {
+        self.buffer
+    }
+}
+
+impl DerefMut for IndentGuard<'_> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.buffer
+    }
+}
+
+impl Drop for IndentGuard<'_> {
+    fn drop(&mut self) {
+        self.buffer.indent_left();
+    }
+}
+
+/// A [BlockGuard] keeps a block of code open in a [CodeBuffer] for as long as
+/// it is alive.
+///
+/// It's created by calling [CodeBuffer::block()],
+/// [CodeBuffer::block_with_close()] or [CodeBuffer::delimited()]. The body of the block is indented, and
+/// when the guard is dropped the indentation level is returned and the
+/// closing line is written. The guard dereferences to the [CodeBuffer] it
+/// borrows, so you write the body through the guard itself.
+///
+/// # Example
+/// ```
+/// use simplegen::CodeBuffer;
+///
+/// let mut buffer = CodeBuffer::new(4);
+///
+/// {
+///     let mut body = buffer.block("fn main()");
+///     body.println("println!(\"Hello, World!\");");
+/// }
+///
+/// assert_eq!(
+///     "fn main() {\n    println!(\"Hello, World!\");\n}",
+///     buffer.to_string()
+/// );
+/// ```
+pub struct BlockGuard<'a> {
+    /// The buffer that the block was opened in.
+    buffer: &'a mut CodeBuffer,
+    /// Line written when the block is closed.
+    close: String,
+}
+
+impl<'a> BlockGuard<'a> {
+    /// Write the opening line of a block and indent the buffer right. The
+    /// closing line is written when the guard is dropped.
+    pub(crate) fn new(buffer: &'a mut CodeBuffer, open: &str, close: &str) -> Self {
+        buffer.println(open);
+        buffer.indent_right();
+        BlockGuard {
+            buffer,
+            close: close.to_string(),
+        }
+    }
+}
+
+impl Deref for BlockGuard<'_> {
+    type Target = CodeBuffer;
+
+    fn deref(&self) -> &Self::Target {
+        self.buffer
+    }
+}
+
+impl DerefMut for BlockGuard<'_> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.buffer
+    }
+}
+
+impl Drop for BlockGuard<'_> {
+    fn drop(&mut self) {
+        self.buffer.println_left(&self.close);
+    }
+}
+
+/// A [RegionGuard] keeps a named, foldable region open in a [CodeBuffer] for
+/// as long as it is alive.
+///
+/// It's created by calling [CodeBuffer::region()]. The region's closing
+/// marker is written when the guard is dropped. Regions don't change the
+/// indentation level. The guard dereferences to the [CodeBuffer] it borrows,
+/// so you write the region's contents through the guard itself.
+///
+/// # Example
+/// ```
+/// use simplegen::CodeBuffer;
+///
+/// let mut buffer = CodeBuffer::new(4);
+///
+/// {
+///     let mut region = buffer.region("Constants");
+///     region.println("const A: u32 = 1;");
+/// }
+///
+/// assert_eq!(
+///     "// region: Constants\nconst A: u32 = 1;\n// endregion",
+///     buffer.to_string()
+/// );
+/// ```
+pub struct RegionGuard<'a> {
+    /// The buffer that the region was opened in.
+    buffer: &'a mut CodeBuffer,
+    /// Marker written when the region is closed.
+    close: String,
+}
+
+impl<'a> RegionGuard<'a> {
+    /// Write the opening marker of a region. The closing marker is written
+    /// when the guard is dropped.
+    pub(crate) fn new(buffer: &'a mut CodeBuffer, open: &str, close: &str) -> Self {
+        buffer.println(open);
+        RegionGuard {
+            buffer,
+            close: close.to_string(),
+        }
+    }
+}
+
+impl Deref for RegionGuard<'_> {
+    type Target = CodeBuffer;
+
+    fn deref(&self) -> &Self::Target {
+        self.buffer
+    }
+}
+
+impl DerefMut for RegionGuard<'_> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.buffer
+    }
+}
+
+impl Drop for RegionGuard<'_> {
+    fn drop(&mut self) {
+        self.buffer.println(&self.close);
+    }
+}
+
+/// An [ElementGuard] keeps an XML or HTML element open in a [CodeBuffer] for
+/// as long as it is alive.
+///
+/// It's created by calling [CodeBuffer::element()], which writes the start
+/// tag. The element's content is indented, and when the guard is dropped the
+/// indentation level is returned and the end tag is written. The guard
+/// dereferences to the [CodeBuffer] it borrows, so you write the content
+/// through the guard itself.
+///
+/// # Example
+/// ```
+/// use simplegen::CodeBuffer;
+///
+/// let mut buffer = CodeBuffer::new(2);
+///
+/// {
+///     let mut list = buffer.element("ul", &[]);
+///     list.println("<li>One</li>");
+/// }
+///
+/// assert_eq!("<ul>\n  <li>One</li>\n</ul>", buffer.to_string());
+/// ```
+pub struct ElementGuard<'a> {
+    /// The buffer that the element was opened in.
+    buffer: &'a mut CodeBuffer,
+    /// End tag written when the element is closed.
+    end_tag: String,
+}
+
+impl<'a> ElementGuard<'a> {
+    /// Write the start tag of an element and indent the buffer right. The end
+    /// tag for `tag` is written when the guard is dropped.
+    pub(crate) fn new(buffer: &'a mut CodeBuffer, start_tag: &str, tag: &str) -> Self {
+        buffer.println(start_tag);
+        buffer.indent_right();
+        ElementGuard {
+            buffer,
+            end_tag: format!("</{}>", tag),
+        }
+    }
+}
+
+impl Deref for ElementGuard<'_> {
+    type Target = CodeBuffer;
+
+    fn deref(&self) -> &Self::Target {
+        self.buffer
+    }
+}
+
+impl DerefMut for ElementGuard<'_> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.buffer
+    }
+}
+
+impl Drop for ElementGuard<'_> {
+    fn drop(&mut self) {
+        self.buffer.println_left(&self.end_tag);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::code_buffer::CodeBuffer;
+
+    #[test]
+    fn guard_should_indent_then_unindent_on_drop() {
+        let mut printer = CodeBuffer::new(4);
+        {
+            let mut guard = printer.indented();
+            guard.println("testing");
+        }
+        printer.println("testing");
+        let actual = printer.to_string();
+
+        assert_eq!("    testing\ntesting", actual);
+    }
+
+    #[test]
+    fn nested_guards_should_stack() {
+        let mut printer = CodeBuffer::new(4);
+
+        let expected = "a {\n    b {\n        c\n    }\n}";
+
+        printer.println("a {");
+        {
+            let mut outer = printer.indented();
+            outer.println("b {");
+            {
+                let mut inner = outer.indented();
+                inner.println("c");
+            }
+            outer.println("}");
+        }
+        printer.println("}");
+        let actual = printer.to_string();
+
+        assert_eq!(expected, actual);
+    }
+
+    #[test]
+    fn guard_should_live_across_loop() {
+        let mut printer = CodeBuffer::new(2);
+
+        let expected = "[\n  1\n  2\n  3\n]";
+
+        printer.println("[");
+        {
+            let mut guard = printer.indented();
+            for number in 1..=3 {
+                guard.println(number.to_string().as_str());
+            }
+        }
+        printer.println("]");
+        let actual = printer.to_string();
+
+        assert_eq!(expected, actual);
+    }
+
+    #[test]
+    fn guard_should_unindent_on_early_return() {
+        fn write_body(printer: &mut CodeBuffer) -> Option<()> {
+            let mut guard = printer.indented();
+            guard.println("early");
+            None?;
+            guard.println("unreachable");
+            Some(())
+        }
+
+        let mut printer = CodeBuffer::new(4);
+        write_body(&mut printer);
+        printer.println("after");
+        let actual = printer.to_string();
+
+        assert_eq!("    early\nafter", actual);
+    }
+
+    #[test]
+    fn block_should_write_braces_around_indented_body() {
+        let mut printer = CodeBuffer::new(4);
+        {
+            let mut block = printer.block("fn main()");
+            block.println("testing");
+        }
+        let actual = printer.to_string();
+
+        assert_eq!("fn main() {\n    testing\n}", actual);
+    }
+
+    #[test]
+    fn nested_blocks_should_indent_braces() {
+        let mut printer = CodeBuffer::new(4);
+
+        let expected = "fn main() {\n    if true {\n        testing\n    }\n}";
+
+        {
+            let mut outer = printer.block("fn main()");
+            let mut inner = outer.block("if true");
+            inner.println("testing");
+        }
+        let actual = printer.to_string();
+
+        assert_eq!(expected, actual);
+    }
+
+    #[test]
+    fn block_with_close_should_write_custom_closing_line() {
+        let mut printer = CodeBuffer::new(4);
+        {
+            let mut block = printer.block_with_close("let point = Point", "};");
+            block.println("x: 1,");
+        }
+        let actual = printer.to_string();
+
+        assert_eq!("let point = Point {\n    x: 1,\n};", actual);
+    }
+
+    #[test]
+    fn nested_regions_should_close_in_order() {
+        let mut printer = CodeBuffer::new(4);
+
+        let expected = "// region: Outer\n// region: Inner\ntesting\n// endregion\n// endregion";
+
+        {
+            let mut outer = printer.region("Outer");
+            let mut inner = outer.region("Inner");
+            inner.println("testing");
+        }
+        let actual = printer.to_string();
+
+        assert_eq!(expected, actual);
+    }
+
+    #[test]
+    fn region_should_use_configured_markers() {
+        let mut printer = CodeBuffer::new(4);
+        printer.set_region_markers("#region {name}", "#endregion // {name}");
+        printer.indent_right();
+        {
+            let mut region = printer.region("Fields");
+            region.println("int x;");
+        }
+        let actual = printer.to_string();
+
+        assert_eq!(
+            "    #region Fields\n    int x;\n    #endregion // Fields",
+            actual
+        );
+    }
+
+    #[test]
+    fn delimited_should_write_custom_delimiters() {
+        let mut printer = CodeBuffer::new(4);
+
+        let expected = "let arr = [\n    1,\n    2,\n];";
+
+        {
+            let mut array = printer.delimited("let arr =", " [", "];");
+            array.println("1,");
+            array.println("2,");
+        }
+        let actual = printer.to_string();
+
+        assert_eq!(expected, actual);
+    }
+}
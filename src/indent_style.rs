@@ -0,0 +1,228 @@
+use alloc::collections::BTreeMap;
+use alloc::string::String;
+
+use crate::width::text_width;
+
+/// The text a [CodeBuffer](crate::CodeBuffer) writes for each level of
+/// indentation.
+///
+/// # Example
+/// ```
+/// use simplegen::{CodeBuffer, IndentStyle};
+///
+/// let mut buffer = CodeBuffer::with_style(IndentStyle::Tabs);
+///
+/// buffer.println("all:");
+/// buffer.println_right("cc main.c");
+///
+/// assert_eq!("all:\n\tcc main.c", buffer.to_string());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum IndentStyle {
+    /// Indent each level by the given number of spaces.
+    Spaces(u32),
+    /// Indent each level by a single tab character.
+    Tabs,
+    /// Indent each level by repeating the given string, such as `"│   "` for
+    /// drawing a tree.
+    Unit(String),
+}
+
+impl Default for IndentStyle {
+    /// The default indentation style is 4 spaces.
+    fn default() -> Self {
+        IndentStyle::Spaces(4)
+    }
+}
+
+/// Spaces that indentation prefixes are copied from, so building a prefix
+/// doesn't need its own allocation.
+const SPACES: &str = "                                                                ";
+
+/// Tabs that indentation prefixes are copied from.
+const TABS: &str = "\t\t\t\t\t\t\t\t\t\t\t\t\t\t\t\t";
+
+impl IndentStyle {
+    /// Build the whitespace prefix for a line at the given indentation level.
+    pub(crate) fn prefix(&self, level: u32) -> String {
+        let mut prefix = String::with_capacity(self.prefix_len(level));
+        self.push_prefix(level, &mut prefix);
+        prefix
+    }
+
+    /// Get the length in bytes of the whitespace prefix for a line at the
+    /// given indentation level.
+    pub(crate) fn prefix_len(&self, level: u32) -> usize {
+        match self {
+            IndentStyle::Spaces(width) => (*width as usize).saturating_mul(level as usize),
+            IndentStyle::Tabs => level as usize,
+            IndentStyle::Unit(unit) => unit.len().saturating_mul(level as usize),
+        }
+    }
+
+    /// Get the number of characters written for each level of indentation.
+    pub(crate) fn unit_width(&self) -> u32 {
+        match self {
+            IndentStyle::Spaces(width) => *width,
+            IndentStyle::Tabs => 1,
+            IndentStyle::Unit(unit) => text_width(unit) as u32,
+        }
+    }
+
+    /// Get the position in `line` where its content starts, after any
+    /// indentation. Whole units of an [IndentStyle::Unit] are skipped before
+    /// any spaces and tabs, so units that aren't whitespace aren't mistaken
+    /// for content.
+    pub(crate) fn content_start(&self, line: &str) -> usize {
+        let mut rest = line;
+        if let IndentStyle::Unit(unit) = self {
+            if !unit.is_empty() {
+                while let Some(after) = rest.strip_prefix(unit.as_str()) {
+                    rest = after;
+                }
+            }
+        }
+        line.len() - rest.trim_start_matches([' ', '\t']).len()
+    }
+
+    /// Append the whitespace prefix for a line at the given indentation level
+    /// to a string.
+    pub(crate) fn push_prefix(&self, level: u32, out: &mut String) {
+        let chunk = match self {
+            IndentStyle::Spaces(_) => SPACES,
+            IndentStyle::Tabs => TABS,
+            IndentStyle::Unit(unit) if unit.is_empty() => return,
+            IndentStyle::Unit(unit) => {
+                for _ in 0..level {
+                    out.push_str(unit);
+                }
+                return;
+            }
+        };
+
+        let mut remaining = self.prefix_len(level);
+        while remaining > 0 {
+            let len = remaining.min(chunk.len());
+            out.push_str(&chunk[..len]);
+            remaining -= len;
+        }
+    }
+}
+
+/// Detect the [IndentStyle] used by a sample of code, so generated code can
+/// be indented to match the code around it.
+///
+/// Tabs are detected when more lines are indented with tabs than with spaces.
+/// Otherwise the number of spaces per level is the most common increase in
+/// indentation from one line with content to the next. Samples without any
+/// indentation give the default style.
+///
+/// # Arguments
+///
+/// * `sample` - Code to inspect.
+///
+/// # Examples
+///
+/// ```
+/// use simplegen::{detect_indent, CodeBuffer, IndentStyle};
+///
+/// let style = detect_indent("fn main() {\n  run();\n}");
+/// assert_eq!(IndentStyle::Spaces(2), style);
+///
+/// let mut buffer = CodeBuffer::with_style(style);
+/// ```
+pub fn detect_indent(sample: &str) -> IndentStyle {
+    let mut tab_lines = 0;
+    let mut space_lines = 0;
+    let mut increments: BTreeMap<usize, usize> = BTreeMap::new();
+    let mut previous_spaces = 0;
+
+    for line in sample.lines().filter(|line| !line.trim().is_empty()) {
+        if line.starts_with('\t') {
+            tab_lines += 1;
+            continue;
+        }
+
+        let spaces = line.len() - line.trim_start_matches(' ').len();
+        if spaces > 0 {
+            space_lines += 1;
+        }
+        if spaces > previous_spaces {
+            *increments.entry(spaces - previous_spaces).or_insert(0) += 1;
+        }
+        previous_spaces = spaces;
+    }
+
+    if tab_lines > space_lines {
+        return IndentStyle::Tabs;
+    }
+
+    // Ties go to the smallest increment.
+    let most_common = increments
+        .iter()
+        .max_by(|a, b| a.1.cmp(b.1).then(b.0.cmp(a.0)));
+    match most_common {
+        Some((&increment, _)) => IndentStyle::Spaces(increment as u32),
+        None => IndentStyle::default(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::indent_style::{detect_indent, IndentStyle};
+
+    #[test]
+    fn prefix_should_match_repeated_whitespace() {
+        for level in 0..40 {
+            let spaces = IndentStyle::Spaces(3).prefix(level);
+            let tabs = IndentStyle::Tabs.prefix(level);
+
+            assert_eq!(" ".repeat(3 * level as usize), spaces);
+            assert_eq!("\t".repeat(level as usize), tabs);
+        }
+    }
+
+    #[test]
+    fn unit_prefix_should_repeat_unit_per_level() {
+        let style = IndentStyle::Unit("│   ".to_string());
+
+        assert_eq!("│   │   ", style.prefix(2));
+        assert_eq!(style.prefix(2).len(), style.prefix_len(2));
+        assert_eq!(4, style.unit_width());
+    }
+
+    #[test]
+    fn empty_unit_prefix_should_be_empty_at_any_level() {
+        let style = IndentStyle::Unit(String::new());
+
+        assert_eq!("", style.prefix(u32::MAX));
+    }
+
+    #[test]
+    fn detect_indent_should_find_two_spaces() {
+        let sample = "fn main() {\n  if x {\n    run();\n  }\n\n  stop(a,\n       b);\n}";
+
+        assert_eq!(IndentStyle::Spaces(2), detect_indent(sample));
+    }
+
+    #[test]
+    fn detect_indent_should_find_four_spaces() {
+        let sample = "class A:\n    def f(self):\n        pass\n    def g(self):\n        pass";
+
+        assert_eq!(IndentStyle::Spaces(4), detect_indent(sample));
+    }
+
+    #[test]
+    fn detect_indent_should_find_tabs() {
+        let sample = "all:\n\tcc main.c\n\tcc util.c";
+
+        assert_eq!(IndentStyle::Tabs, detect_indent(sample));
+    }
+
+    #[test]
+    fn detect_indent_should_default_without_indentation() {
+        assert_eq!(IndentStyle::default(), detect_indent(""));
+        assert_eq!(IndentStyle::default(), detect_indent("a\nb"));
+    }
+}
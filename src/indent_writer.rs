@@ -0,0 +1,163 @@
+use std::io::{self, Write};
+
+use crate::IndentStyle;
+
+/// An [IndentWriter] wraps an existing [io::Write] and indents every line
+/// written through it.
+///
+/// The indentation prefix is written whenever a write begins a new line, so
+/// it can be composed with anything that writes to an [io::Write], such as a
+/// serializer. Empty lines, ending in either `\n` or `\r\n`, aren't indented,
+/// so they never contain trailing whitespace.
+///
+/// # Example
+/// ```
+/// use simplegen::IndentWriter;
+/// use std::io::Write;
+///
+/// let mut writer = IndentWriter::new(Vec::new(), 4);
+///
+/// writeln!(writer, "fn main() {{").unwrap();
+/// writer.indent_right();
+/// writeln!(writer, "let x = 1;\nlet y = 2;").unwrap();
+/// writer.indent_left();
+/// write!(writer, "}}").unwrap();
+///
+/// let bytes = writer.into_inner();
+/// assert_eq!(
+///     b"fn main() {\n    let x = 1;\n    let y = 2;\n}",
+///     bytes.as_slice()
+/// );
+/// ```
+pub struct IndentWriter<W: Write> {
+    /// Writer that indented output is written to.
+    inner: W,
+    /// Whitespace to indent lines by for each level.
+    style: IndentStyle,
+    /// Level of indentation of the current line.
+    level: u32,
+    /// Whether the next byte written starts a new line.
+    at_line_start: bool,
+}
+
+impl<W: Write> IndentWriter<W> {
+    /// Create a new `IndentWriter` that writes to `inner`.
+    ///
+    /// # Arguments
+    ///
+    /// * `inner` - Writer to write indented output to.
+    /// * `indent` - Number of spaces to indent by.
+    pub fn new(inner: W, indent: u32) -> Self {
+        IndentWriter::with_style(inner, IndentStyle::Spaces(indent))
+    }
+
+    /// Create a new `IndentWriter` that writes to `inner` and indents with
+    /// the given [IndentStyle].
+    ///
+    /// # Arguments
+    ///
+    /// * `inner` - Writer to write indented output to.
+    /// * `style` - Whitespace to indent by for each level.
+    pub fn with_style(inner: W, style: IndentStyle) -> Self {
+        IndentWriter {
+            inner,
+            style,
+            level: 0,
+            at_line_start: true,
+        }
+    }
+
+    /// Get the current indentation level.
+    pub fn level(&self) -> u32 {
+        self.level
+    }
+
+    /// Indent right. This takes effect from the next line that is started.
+    pub fn indent_right(&mut self) -> &mut Self {
+        self.level = self.level.saturating_add(1);
+        self
+    }
+
+    /// Indent left. This takes effect from the next line that is started. The
+    /// indentation level never goes below 0.
+    pub fn indent_left(&mut self) -> &mut Self {
+        if self.level > 0 {
+            self.level -= 1;
+        }
+        self
+    }
+
+    /// Return the wrapped writer.
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+}
+
+impl<W: Write> Write for IndentWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        for line in buf.split_inclusive(|&byte| byte == b'\n') {
+            let blank = line == b"\n" || line == b"\r\n";
+            if self.at_line_start && !blank {
+                let prefix = self.style.prefix(self.level);
+                self.inner.write_all(prefix.as_bytes())?;
+            }
+            self.inner.write_all(line)?;
+            self.at_line_start = line.ends_with(b"\n");
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::indent_writer::IndentWriter;
+    use std::io::Write;
+
+    #[test]
+    fn should_indent_nested_multi_line_content() {
+        let mut writer = IndentWriter::new(Vec::new(), 2);
+
+        let expected = "a {\n  b {\n    c\n\n    d\n  }\n}\n";
+
+        writeln!(writer, "a {{").unwrap();
+        writer.indent_right();
+        writeln!(writer, "b {{").unwrap();
+        writer.indent_right();
+        writer.write_all(b"c\n\nd\n").unwrap();
+        writer.indent_left();
+        writeln!(writer, "}}").unwrap();
+        writer.indent_left();
+        writeln!(writer, "}}").unwrap();
+        let actual = String::from_utf8(writer.into_inner()).unwrap();
+
+        assert_eq!(expected, actual);
+    }
+
+    #[test]
+    fn should_only_indent_at_start_of_line() {
+        let mut writer = IndentWriter::new(Vec::new(), 4);
+        writer.indent_right();
+        write!(writer, "let x").unwrap();
+        write!(writer, " = 1;").unwrap();
+        let actual = String::from_utf8(writer.into_inner()).unwrap();
+
+        assert_eq!("    let x = 1;", actual);
+    }
+
+    #[test]
+    fn should_not_indent_empty_crlf_lines() {
+        let mut writer = IndentWriter::new(Vec::new(), 2);
+
+        let expected = "  a\r\n\r\n  b\r\n";
+
+        writer.indent_right();
+        writer.write_all(b"a\r\n\r\nb\r\n").unwrap();
+        let actual = String::from_utf8(writer.into_inner()).unwrap();
+
+        assert_eq!(expected, actual);
+    }
+}
@@ -1,3 +1,54 @@
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+
+extern crate alloc;
+
+mod brace_style;
+mod checkpoint;
 mod code_buffer;
+mod code_buffer_builder;
+mod code_sink;
+mod counter_list;
+mod diff;
+mod error;
+pub mod escape;
+mod guard;
+mod indent_style;
+#[cfg(feature = "std")]
+mod indent_writer;
+mod line_ending;
+mod list_builder;
+mod macros;
+mod match_builder;
+pub mod pad;
+#[cfg(feature = "std")]
+mod streaming_printer;
+mod switch_builder;
+mod table;
+mod template;
+mod width;
+mod wrap;
 
+pub use brace_style::BraceStyle;
+pub use checkpoint::Checkpoint;
 pub use code_buffer::CodeBuffer;
+pub use code_buffer_builder::CodeBufferBuilder;
+pub use code_sink::CodeSink;
+pub use counter_list::CounterList;
+pub use diff::DiffLine;
+pub use error::{EmptyBufferError, UnbalancedIndentError};
+pub use guard::{BlockGuard, ElementGuard, IndentGuard, RegionGuard};
+pub use indent_style::{detect_indent, IndentStyle};
+#[cfg(feature = "std")]
+pub use indent_writer::IndentWriter;
+pub use line_ending::LineEnding;
+pub use list_builder::ListBuilder;
+pub use match_builder::MatchBuilder;
+#[cfg(feature = "std")]
+pub use streaming_printer::StreamingPrinter;
+pub use switch_builder::SwitchBuilder;
+pub use table::Table;
+pub use template::TemplateContext;
+
+/// The old name of [CodeBuffer], which it has been merged into.
+#[deprecated(note = "use `CodeBuffer` instead")]
+pub type IndentedPrinter = CodeBuffer;
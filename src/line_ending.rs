@@ -0,0 +1,39 @@
+/// The separator a [CodeBuffer](crate::CodeBuffer) places between lines.
+///
+/// # Example
+/// ```
+/// use simplegen::{CodeBuffer, LineEnding};
+///
+/// let mut buffer = CodeBuffer::default();
+/// buffer.set_line_ending(LineEnding::Crlf);
+///
+/// buffer.println("@echo off");
+/// buffer.println("echo Hello, World!");
+///
+/// assert_eq!("@echo off\r\necho Hello, World!", buffer.to_string());
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum LineEnding {
+    /// Unix style line endings, `\n`.
+    Lf,
+    /// Windows style line endings, `\r\n`.
+    Crlf,
+}
+
+impl Default for LineEnding {
+    /// The default line ending is `\n`.
+    fn default() -> Self {
+        LineEnding::Lf
+    }
+}
+
+impl LineEnding {
+    /// The characters that make up this line ending.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LineEnding::Lf => "\n",
+            LineEnding::Crlf => "\r\n",
+        }
+    }
+}
@@ -0,0 +1,172 @@
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+use crate::CodeBuffer;
+
+/// A [ListBuilder] writes the items of a list literal to a [CodeBuffer], one
+/// per line, with commas between them.
+///
+/// It's created by calling [CodeBuffer::list()]. Items are collected until
+/// [ListBuilder::finish()] is called, so the builder knows which item is last
+/// and only puts a comma after it if trailing commas are turned on. The
+/// builder is passed along by value, so `finish` can only be called once.
+///
+/// # Example
+/// ```
+/// use simplegen::CodeBuffer;
+///
+/// let mut buffer = CodeBuffer::new(4);
+///
+/// buffer.println("let primes = [");
+/// buffer.indent_right();
+/// buffer.list().item("2").item("3").item("5").finish();
+/// buffer.println_left("];");
+///
+/// assert_eq!("let primes = [\n    2,\n    3,\n    5\n];", buffer.to_string());
+/// ```
+#[must_use = "the list isn't written until `finish()` is called"]
+pub struct ListBuilder<'a> {
+    /// The buffer that the list is written to.
+    buffer: &'a mut CodeBuffer,
+    /// Items of the list, in the order they were added.
+    items: Vec<String>,
+    /// Whether a comma is written after the last item.
+    trailing_comma: bool,
+}
+
+impl<'a> ListBuilder<'a> {
+    /// Create a builder that writes a list to `buffer`, without a trailing
+    /// comma.
+    pub(crate) fn new(buffer: &'a mut CodeBuffer) -> Self {
+        ListBuilder {
+            buffer,
+            items: Vec::new(),
+            trailing_comma: false,
+        }
+    }
+
+    /// Set whether a comma is written after the last item. This is off by
+    /// default.
+    ///
+    /// # Arguments
+    ///
+    /// * `trailing_comma` - Whether to end the last item with a comma.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use simplegen::CodeBuffer;
+    ///
+    /// let mut buffer = CodeBuffer::default();
+    /// buffer.list().trailing_comma(true).item("a").item("b").finish();
+    ///
+    /// assert_eq!("a,\nb,", buffer.to_string());
+    /// ```
+    pub fn trailing_comma(mut self, trailing_comma: bool) -> Self {
+        self.trailing_comma = trailing_comma;
+        self
+    }
+
+    /// Add an item to the list.
+    ///
+    /// # Arguments
+    ///
+    /// * `item` - Code for the item, without a comma.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use simplegen::CodeBuffer;
+    ///
+    /// let mut buffer = CodeBuffer::default();
+    /// let mut list = buffer.list();
+    /// for name in &["a", "b"] {
+    ///     list = list.item(name);
+    /// }
+    /// list.finish();
+    ///
+    /// assert_eq!("a,\nb", buffer.to_string());
+    /// ```
+    pub fn item(mut self, item: &str) -> Self {
+        self.items.push(item.to_string());
+        self
+    }
+
+    /// Write the items to the buffer at its current indentation level, each
+    /// on its own line.
+    pub fn finish(self) {
+        let last = self.items.len().saturating_sub(1);
+        for (i, item) in self.items.into_iter().enumerate() {
+            if i < last || self.trailing_comma {
+                self.buffer.println(&(item + ","));
+            } else {
+                self.buffer.println(&item);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::CodeBuffer;
+
+    #[test]
+    fn should_separate_items_without_trailing_comma() {
+        let mut printer = CodeBuffer::new(4);
+
+        let expected = "[\n    a,\n    b,\n    c\n]";
+
+        printer.println("[");
+        printer.indent_right();
+        printer.list().item("a").item("b").item("c").finish();
+        printer.println_left("]");
+        let actual = printer.to_string();
+
+        assert_eq!(expected, actual);
+    }
+
+    #[test]
+    fn should_end_every_item_with_trailing_comma() {
+        let mut printer = CodeBuffer::new(4);
+
+        let expected = "[\n    a,\n    b,\n    c,\n]";
+
+        printer.println("[");
+        printer.indent_right();
+        printer
+            .list()
+            .trailing_comma(true)
+            .item("a")
+            .item("b")
+            .item("c")
+            .finish();
+        printer.println_left("]");
+        let actual = printer.to_string();
+
+        assert_eq!(expected, actual);
+    }
+
+    #[test]
+    fn should_collect_items_added_in_loop() {
+        let mut printer = CodeBuffer::new(4);
+
+        let mut list = printer.list().trailing_comma(true);
+        for number in 1..=3 {
+            list = list.item(&number.to_string());
+        }
+        list.finish();
+        let actual = printer.to_string();
+
+        assert_eq!("1,\n2,\n3,", actual);
+    }
+
+    #[test]
+    fn empty_list_should_write_nothing() {
+        let mut printer = CodeBuffer::new(4);
+
+        printer.list().trailing_comma(true).finish();
+        let actual = printer.to_string();
+
+        assert_eq!("", actual);
+    }
+}
@@ -0,0 +1,24 @@
+/// Write a formatted line to a [CodeBuffer](crate::CodeBuffer) at its current
+/// indentation level.
+///
+/// This expands to a call to
+/// [CodeBuffer::println_fmt()](crate::CodeBuffer::println_fmt), so the
+/// arguments are formatted straight into the buffered line.
+///
+/// # Example
+/// ```
+/// use simplegen::{println_fmt, CodeBuffer};
+///
+/// let mut buffer = CodeBuffer::default();
+/// let name = "x";
+///
+/// println_fmt!(buffer, "let {} = {};", name, 1);
+///
+/// assert_eq!("let x = 1;", buffer.to_string());
+/// ```
+#[macro_export]
+macro_rules! println_fmt {
+    ($buffer:expr, $($arg:tt)*) => {{
+        $buffer.println_fmt(format_args!($($arg)*));
+    }};
+}
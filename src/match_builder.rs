@@ -0,0 +1,178 @@
+use alloc::format;
+
+use crate::CodeBuffer;
+
+/// A [MatchBuilder] writes a Rust `match` expression to a [CodeBuffer], one
+/// arm at a time.
+///
+/// It's created by calling [CodeBuffer::match_on()], which opens the
+/// `match scrutinee` block in the buffer's [BraceStyle](crate::BraceStyle).
+/// Each arm is written with its `=>` and trailing comma, and the `match` is
+/// closed by [MatchBuilder::finish()]. The builder is passed along by value,
+/// so `finish` can only be called once.
+///
+/// # Example
+/// ```
+/// use simplegen::CodeBuffer;
+///
+/// let mut buffer = CodeBuffer::new(4);
+///
+/// buffer
+///     .match_on("token")
+///     .arm_expr("Token::Plus", "a + b")
+///     .arm("_", |buffer| {
+///         buffer.println("panic!(\"unexpected token\")");
+///     })
+///     .finish();
+///
+/// assert_eq!(
+///     "match token {\n    Token::Plus => a + b,\n    _ => {\n        panic!(\"unexpected token\")\n    },\n}",
+///     buffer.to_string()
+/// );
+/// ```
+///
+/// Arms can be written in a loop by reassigning the builder:
+/// ```
+/// use simplegen::CodeBuffer;
+///
+/// let mut buffer = CodeBuffer::new(4);
+///
+/// let mut arms = buffer.match_on("n");
+/// for n in 0..2 {
+///     arms = arms.arm_expr(&n.to_string(), "small()");
+/// }
+/// arms.arm_expr("_", "large()").finish();
+///
+/// assert_eq!(
+///     "match n {\n    0 => small(),\n    1 => small(),\n    _ => large(),\n}",
+///     buffer.to_string()
+/// );
+/// ```
+#[must_use = "the match isn't closed until `finish()` is called"]
+pub struct MatchBuilder<'a> {
+    /// The buffer that the `match` is written to.
+    buffer: &'a mut CodeBuffer,
+}
+
+impl<'a> MatchBuilder<'a> {
+    /// Open a `match` on `scrutinee` in `buffer`, and return a builder that
+    /// writes its arms.
+    pub(crate) fn new(buffer: &'a mut CodeBuffer, scrutinee: &str) -> Self {
+        buffer.open_brace(&format!("match {}", scrutinee));
+        MatchBuilder { buffer }
+    }
+
+    /// Write an arm with a block body. `f` writes the body of the block, which
+    /// is indented one level right of the pattern.
+    ///
+    /// # Arguments
+    ///
+    /// * `pattern` - Pattern the arm matches, including any guard.
+    /// * `f` - Function that writes the body of the arm.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use simplegen::CodeBuffer;
+    ///
+    /// let mut buffer = CodeBuffer::new(4);
+    /// buffer
+    ///     .match_on("x")
+    ///     .arm("0", |buffer| {
+    ///         buffer.println("zero()");
+    ///     })
+    ///     .finish();
+    ///
+    /// assert_eq!(
+    ///     "match x {\n    0 => {\n        zero()\n    },\n}",
+    ///     buffer.to_string()
+    /// );
+    /// ```
+    pub fn arm<F: FnOnce(&mut CodeBuffer)>(self, pattern: &str, f: F) -> Self {
+        self.buffer.println(&format!("{} => {{", pattern));
+        self.buffer.indent_right();
+        f(self.buffer);
+        self.buffer.println_left("},");
+        self
+    }
+
+    /// Write an arm with a single expression as its body, on one line.
+    ///
+    /// # Arguments
+    ///
+    /// * `pattern` - Pattern the arm matches, including any guard.
+    /// * `expr` - Expression the arm evaluates to.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use simplegen::CodeBuffer;
+    ///
+    /// let mut buffer = CodeBuffer::new(4);
+    /// buffer.match_on("x").arm_expr("0", "zero()").finish();
+    ///
+    /// assert_eq!("match x {\n    0 => zero(),\n}", buffer.to_string());
+    /// ```
+    pub fn arm_expr(self, pattern: &str, expr: &str) -> Self {
+        self.buffer.println(&format!("{} => {},", pattern, expr));
+        self
+    }
+
+    /// Close the `match` expression, returning the buffer to the indentation
+    /// level it was at before [CodeBuffer::match_on()] was called.
+    pub fn finish(self) {
+        self.buffer.println_left("}");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{BraceStyle, CodeBuffer};
+
+    #[test]
+    fn should_write_arms_with_commas() {
+        let mut printer = CodeBuffer::new(4);
+
+        let expected = "fn f(x: u8) -> u8 {
+    match x {
+        0 => 1,
+        n => {
+            let y = n * 2;
+            y + 1
+        },
+    }
+}";
+
+        printer.println("fn f(x: u8) -> u8 {");
+        printer.indent_right();
+        printer
+            .match_on("x")
+            .arm_expr("0", "1")
+            .arm("n", |printer| {
+                printer.println("let y = n * 2;");
+                printer.println("y + 1");
+            })
+            .finish();
+        printer.println_left("}");
+        let actual = printer.to_string();
+
+        assert_eq!(expected, actual);
+        assert_eq!(0, printer.level());
+    }
+
+    #[test]
+    fn should_open_match_in_brace_style() {
+        let mut printer = CodeBuffer::new(4);
+        printer.set_brace_style(BraceStyle::Allman);
+
+        let expected = "match x\n{\n    _ => (),\n}";
+
+        let mut arms = printer.match_on("x");
+        arms = arms.arm_expr("_", "()");
+        arms.finish();
+        let actual = printer.to_string();
+
+        assert_eq!(expected, actual);
+        assert_eq!(0, printer.level());
+    }
+}
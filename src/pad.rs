@@ -0,0 +1,72 @@
+//! Functions that pad strings to a fixed width, for generating fixed-width
+//! fields and records.
+
+use alloc::string::String;
+
+use crate::width::text_width;
+
+/// Pad a string to `width` characters by adding `fill` characters after it.
+/// A string that is already `width` characters or longer is returned as it
+/// is.
+///
+/// # Arguments
+///
+/// * `s` - String to pad.
+/// * `width` - Width to pad the string to, in characters.
+/// * `fill` - Character to pad with.
+///
+/// # Examples
+///
+/// ```
+/// use simplegen::pad::pad_right;
+///
+/// assert_eq!("ab...", pad_right("ab", 5, '.'));
+/// assert_eq!("abcdef", pad_right("abcdef", 5, '.'));
+/// ```
+pub fn pad_right(s: &str, width: usize, fill: char) -> String {
+    let mut padded = String::from(s);
+    padded.extend(core::iter::repeat(fill).take(width.saturating_sub(text_width(s))));
+    padded
+}
+
+/// Pad a string to `width` characters by adding `fill` characters before it.
+/// A string that is already `width` characters or longer is returned as it
+/// is.
+///
+/// # Arguments
+///
+/// * `s` - String to pad.
+/// * `width` - Width to pad the string to, in characters.
+/// * `fill` - Character to pad with.
+///
+/// # Examples
+///
+/// ```
+/// use simplegen::pad::pad_left;
+///
+/// assert_eq!("00042", pad_left("42", 5, '0'));
+/// ```
+pub fn pad_left(s: &str, width: usize, fill: char) -> String {
+    let mut padded: String = core::iter::repeat(fill)
+        .take(width.saturating_sub(text_width(s)))
+        .collect();
+    padded.push_str(s);
+    padded
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::pad::{pad_left, pad_right};
+
+    #[test]
+    fn should_pad_to_width_in_characters() {
+        assert_eq!("café  ", pad_right("café", 6, ' '));
+        assert_eq!("  café", pad_left("café", 6, ' '));
+    }
+
+    #[test]
+    fn should_leave_long_strings_as_they_are() {
+        assert_eq!("toolong", pad_right("toolong", 3, ' '));
+        assert_eq!("toolong", pad_left("toolong", 3, ' '));
+    }
+}
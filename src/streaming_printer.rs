@@ -0,0 +1,255 @@
+use std::io::{self, BufWriter, Write};
+
+use crate::{CodeSink, IndentStyle, LineEnding};
+
+/// A [StreamingPrinter] writes indented lines straight to an [io::Write]
+/// instead of keeping them in memory.
+///
+/// It maintains indentation level in the same way as a
+/// [CodeBuffer](crate::CodeBuffer), and its output is identical to what
+/// `CodeBuffer::to_string()` would return for the same calls. Lines are
+/// written through a [BufWriter], so [StreamingPrinter::flush()] must be
+/// called once generation is finished.
+///
+/// Writing a line never fails. If the underlying writer returns an error, no
+/// more lines are written, and the error is returned by the next call to
+/// [StreamingPrinter::flush()].
+///
+/// # Example
+/// ```
+/// use simplegen::StreamingPrinter;
+///
+/// let mut bytes: Vec<u8> = Vec::new();
+/// let mut printer = StreamingPrinter::new(&mut bytes, 4);
+///
+/// printer.println("fn add_one(x: u64) -> u64 {");
+/// printer.println_right("x + 1");
+/// printer.println_left("}");
+/// printer.flush().unwrap();
+/// drop(printer);
+///
+/// assert_eq!(b"fn add_one(x: u64) -> u64 {\n    x + 1\n}", bytes.as_slice());
+/// ```
+pub struct StreamingPrinter<W: Write> {
+    /// Buffered writer that lines are written to.
+    writer: BufWriter<W>,
+    /// Whitespace to indent code by for each level.
+    style: IndentStyle,
+    /// Level of indentation of the current line of code.
+    level: u32,
+    /// Separator written between lines.
+    line_ending: LineEnding,
+    /// Whether any line has been written yet, so the next line needs a
+    /// separator before it.
+    started: bool,
+    /// First error returned by the writer, kept until the next flush.
+    error: Option<io::Error>,
+}
+
+impl<W: Write> StreamingPrinter<W> {
+    /// Create a new `StreamingPrinter` that writes to `writer`.
+    ///
+    /// # Arguments
+    ///
+    /// * `writer` - Writer to write lines to.
+    /// * `indent` - Number of spaces to indent by.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use simplegen::StreamingPrinter;
+    /// use std::io;
+    ///
+    /// let mut printer = StreamingPrinter::new(io::stdout(), 4);
+    /// ```
+    pub fn new(writer: W, indent: u32) -> Self {
+        StreamingPrinter::with_style(writer, IndentStyle::Spaces(indent))
+    }
+
+    /// Create a new `StreamingPrinter` that writes to `writer` and indents
+    /// with the given [IndentStyle].
+    ///
+    /// # Arguments
+    ///
+    /// * `writer` - Writer to write lines to.
+    /// * `style` - Whitespace to indent by for each level.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use simplegen::{IndentStyle, StreamingPrinter};
+    /// use std::io;
+    ///
+    /// let mut printer = StreamingPrinter::with_style(io::stdout(), IndentStyle::Tabs);
+    /// ```
+    pub fn with_style(writer: W, style: IndentStyle) -> Self {
+        StreamingPrinter {
+            writer: BufWriter::new(writer),
+            style,
+            level: 0,
+            line_ending: LineEnding::default(),
+            started: false,
+            error: None,
+        }
+    }
+
+    /// Set the separator that is written between lines. The default is
+    /// [LineEnding::Lf].
+    ///
+    /// # Arguments
+    ///
+    /// * `line_ending` - Line ending to separate lines with.
+    pub fn set_line_ending(&mut self, line_ending: LineEnding) {
+        self.line_ending = line_ending;
+    }
+
+    /// Get the current indentation level.
+    pub fn level(&self) -> u32 {
+        self.level
+    }
+
+    /// Write a line at the current indentation level.
+    ///
+    /// # Arguments
+    ///
+    /// * `str` - String to write.
+    pub fn println(&mut self, str: &str) -> &mut Self {
+        if self.error.is_none() {
+            if let Err(error) = self.write_line(str) {
+                self.error = Some(error);
+            }
+        }
+        self
+    }
+
+    /// Indent right.
+    pub fn indent_right(&mut self) -> &mut Self {
+        self.level = self.level.saturating_add(1);
+        self
+    }
+
+    /// Indent left. The indentation level never goes below 0.
+    pub fn indent_left(&mut self) -> &mut Self {
+        if self.level > 0 {
+            self.level -= 1;
+        }
+        self
+    }
+
+    /// Indent right then write a line.
+    ///
+    /// # Arguments
+    ///
+    /// * `str` - String to write.
+    pub fn println_right(&mut self, str: &str) -> &mut Self {
+        self.indent_right().println(str)
+    }
+
+    /// Indent left then write a line.
+    ///
+    /// # Arguments
+    ///
+    /// * `str` - String to write.
+    pub fn println_left(&mut self, str: &str) -> &mut Self {
+        self.indent_left().println(str)
+    }
+
+    /// Flush every line written so far to the underlying writer.
+    ///
+    /// Returns the first error the writer returned since the last flush, if
+    /// there was one.
+    pub fn flush(&mut self) -> io::Result<()> {
+        if let Some(error) = self.error.take() {
+            return Err(error);
+        }
+        self.writer.flush()
+    }
+
+    /// Flush every line written so far, then return the underlying writer.
+    pub fn into_inner(mut self) -> io::Result<W> {
+        self.flush()?;
+        self.writer.into_inner().map_err(|error| error.into_error())
+    }
+
+    /// Write a line, preceded by a separator if it isn't the first line.
+    fn write_line(&mut self, str: &str) -> io::Result<()> {
+        if self.started {
+            self.writer
+                .write_all(self.line_ending.as_str().as_bytes())?;
+        }
+        self.started = true;
+
+        // Blank lines are written empty, like they are by a CodeBuffer.
+        if str.trim().is_empty() {
+            return Ok(());
+        }
+
+        let prefix = self.style.prefix(self.level);
+        self.writer.write_all(prefix.as_bytes())?;
+        self.writer.write_all(str.as_bytes())
+    }
+}
+
+impl<W: Write> CodeSink for StreamingPrinter<W> {
+    fn println(&mut self, str: &str) -> &mut Self {
+        StreamingPrinter::println(self, str)
+    }
+
+    fn indent_right(&mut self) -> &mut Self {
+        StreamingPrinter::indent_right(self)
+    }
+
+    fn indent_left(&mut self) -> &mut Self {
+        StreamingPrinter::indent_left(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::streaming_printer::StreamingPrinter;
+    use crate::{CodeBuffer, LineEnding};
+    use std::io::{self, Write};
+
+    #[test]
+    fn should_match_code_buffer_output() {
+        let mut buffer = CodeBuffer::new(2);
+        buffer.set_line_ending(LineEnding::Crlf);
+        let mut printer = StreamingPrinter::new(Vec::new(), 2);
+        printer.set_line_ending(LineEnding::Crlf);
+
+        buffer.println("[");
+        printer.println("[");
+        for number in 1..=3 {
+            buffer.println_right(&number.to_string()).indent_left();
+            printer.println_right(&number.to_string()).indent_left();
+        }
+        buffer.println_right("").indent_left();
+        printer.println_right("").indent_left();
+        buffer.println("]");
+        printer.println("]");
+
+        let actual = printer.into_inner().unwrap();
+
+        assert_eq!(buffer.to_string().as_bytes(), actual.as_slice());
+    }
+
+    #[test]
+    fn should_report_write_error_on_flush() {
+        struct FailingWriter;
+
+        impl Write for FailingWriter {
+            fn write(&mut self, _: &[u8]) -> io::Result<usize> {
+                Err(io::Error::other("failed"))
+            }
+
+            fn flush(&mut self) -> io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let mut printer = StreamingPrinter::new(FailingWriter, 4);
+        printer.println(&"x".repeat(10_000));
+
+        assert!(printer.flush().is_err());
+    }
+}
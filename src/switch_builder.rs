@@ -0,0 +1,219 @@
+use alloc::format;
+
+use crate::CodeBuffer;
+
+/// A [SwitchBuilder] writes a C-style `switch` statement to a [CodeBuffer],
+/// one case at a time.
+///
+/// It's created by calling [CodeBuffer::switch_on()], which opens the
+/// `switch (expr)` block in the buffer's
+/// [BraceStyle](crate::BraceStyle). Each case label is followed by its
+/// indented body, and the `switch` is closed by [SwitchBuilder::finish()].
+/// The builder is passed along by value, so `finish` can only be called
+/// once.
+///
+/// # Example
+/// ```
+/// use simplegen::CodeBuffer;
+///
+/// let mut buffer = CodeBuffer::new(4);
+///
+/// buffer
+///     .switch_on("op")
+///     .case("ADD", |buffer| {
+///         buffer.println("a += b;");
+///     }, true)
+///     .default(|buffer| {
+///         buffer.println("abort();");
+///     })
+///     .finish();
+///
+/// assert_eq!(
+///     "switch (op) {\n    case ADD:\n        a += b;\n        break;\n    default:\n        abort();\n}",
+///     buffer.to_string()
+/// );
+/// ```
+#[must_use = "the switch isn't closed until `finish()` is called"]
+pub struct SwitchBuilder<'a> {
+    /// The buffer that the `switch` is written to.
+    buffer: &'a mut CodeBuffer,
+}
+
+impl<'a> SwitchBuilder<'a> {
+    /// Open a `switch` on `expr` in `buffer`, and return a builder that
+    /// writes its cases.
+    pub(crate) fn new(buffer: &'a mut CodeBuffer, expr: &str) -> Self {
+        buffer.open_brace(&format!("switch ({})", expr));
+        SwitchBuilder { buffer }
+    }
+
+    /// Write a `case` label and its body. `f` writes the body, which is
+    /// indented one level right of the label. If `auto_break` is true, the
+    /// body is followed by `break;`, otherwise control falls through to the
+    /// next case. A case with an empty body and no break shares the body of
+    /// the case after it.
+    ///
+    /// # Arguments
+    ///
+    /// * `label` - Value the case matches.
+    /// * `f` - Function that writes the body of the case.
+    /// * `auto_break` - Whether to end the body with `break;`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use simplegen::CodeBuffer;
+    ///
+    /// let mut buffer = CodeBuffer::new(4);
+    /// buffer
+    ///     .switch_on("c")
+    ///     .case("'a'", |_| {}, false)
+    ///     .case("'b'", |buffer| {
+    ///         buffer.println("letter();");
+    ///     }, true)
+    ///     .finish();
+    ///
+    /// assert_eq!(
+    ///     "switch (c) {\n    case 'a':\n    case 'b':\n        letter();\n        break;\n}",
+    ///     buffer.to_string()
+    /// );
+    /// ```
+    pub fn case<F: FnOnce(&mut CodeBuffer)>(self, label: &str, f: F, auto_break: bool) -> Self {
+        self.buffer.println(&format!("case {}:", label));
+        self.write_body(f, auto_break)
+    }
+
+    /// Write the `default` label and its body. `f` writes the body, which is
+    /// indented one level right of the label. No `break;` is added, as the
+    /// default case is usually last.
+    ///
+    /// # Arguments
+    ///
+    /// * `f` - Function that writes the body of the default case.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use simplegen::CodeBuffer;
+    ///
+    /// let mut buffer = CodeBuffer::new(4);
+    /// buffer
+    ///     .switch_on("x")
+    ///     .default(|buffer| {
+    ///         buffer.println("return 0;");
+    ///     })
+    ///     .finish();
+    ///
+    /// assert_eq!(
+    ///     "switch (x) {\n    default:\n        return 0;\n}",
+    ///     buffer.to_string()
+    /// );
+    /// ```
+    pub fn default<F: FnOnce(&mut CodeBuffer)>(self, f: F) -> Self {
+        self.buffer.println("default:");
+        self.write_body(f, false)
+    }
+
+    /// Close the `switch` statement, returning the buffer to the indentation
+    /// level it was at before [CodeBuffer::switch_on()] was called.
+    pub fn finish(self) {
+        self.buffer.println_left("}");
+    }
+
+    /// Write the indented body of a case, followed by `break;` if
+    /// `auto_break` is true.
+    fn write_body<F: FnOnce(&mut CodeBuffer)>(self, f: F, auto_break: bool) -> Self {
+        self.buffer.indent_right();
+        f(self.buffer);
+        if auto_break {
+            self.buffer.println("break;");
+        }
+        self.buffer.indent_left();
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{BraceStyle, CodeBuffer};
+
+    #[test]
+    fn should_write_cases_with_breaks_and_default() {
+        let mut printer = CodeBuffer::new(4);
+
+        let expected = "void f(int x) {
+    switch (x) {
+        case 1:
+            one();
+            break;
+        case 2:
+            two();
+        default:
+            other();
+    }
+}";
+
+        printer.println("void f(int x) {");
+        printer.indent_right();
+        printer
+            .switch_on("x")
+            .case(
+                "1",
+                |printer| {
+                    printer.println("one();");
+                },
+                true,
+            )
+            .case(
+                "2",
+                |printer| {
+                    printer.println("two();");
+                },
+                false,
+            )
+            .default(|printer| {
+                printer.println("other();");
+            })
+            .finish();
+        printer.println_left("}");
+        let actual = printer.to_string();
+
+        assert_eq!(expected, actual);
+        assert_eq!(0, printer.level());
+    }
+
+    #[test]
+    fn should_write_cases_from_loop() {
+        let mut printer = CodeBuffer::new(2);
+
+        let expected = "switch (c) {\n  case 'a':\n  case 'b':\n    break;\n}";
+
+        let mut cases = printer.switch_on("c");
+        for (label, last) in [("'a'", false), ("'b'", true)] {
+            cases = cases.case(label, |_| {}, last);
+        }
+        cases.finish();
+        let actual = printer.to_string();
+
+        assert_eq!(expected, actual);
+        assert_eq!(0, printer.level());
+    }
+
+    #[test]
+    fn should_open_switch_in_brace_style() {
+        let mut printer = CodeBuffer::new(2);
+        printer.set_brace_style(BraceStyle::Allman);
+
+        let expected = "switch (x)\n{\n  default:\n    break;\n}";
+
+        printer
+            .switch_on("x")
+            .default(|printer| {
+                printer.println("break;");
+            })
+            .finish();
+        let actual = printer.to_string();
+
+        assert_eq!(expected, actual);
+    }
+}
@@ -0,0 +1,132 @@
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+use crate::width::text_width;
+
+/// A [Table] collects rows of cells so they can be written to a
+/// [CodeBuffer](crate::CodeBuffer) with their columns aligned.
+///
+/// Cells are separated by a single space, and every cell except the last in
+/// its row is padded to the width of the widest cell in its column. Rows may
+/// have different numbers of cells. Missing cells are not padded, so no row
+/// ends in trailing whitespace.
+///
+/// # Example
+/// ```
+/// use simplegen::{CodeBuffer, Table};
+///
+/// let mut table = Table::new();
+/// table.row(&["const A", "= 1;"]);
+/// table.row(&["const LONG", "= 2;"]);
+///
+/// let mut buffer = CodeBuffer::default();
+/// buffer.println_table(&table);
+///
+/// assert_eq!("const A    = 1;\nconst LONG = 2;", buffer.to_string());
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Table {
+    /// Rows of cells, in the order they were added.
+    rows: Vec<Vec<String>>,
+}
+
+impl Table {
+    /// Create a new, empty `Table`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use simplegen::Table;
+    ///
+    /// let table = Table::new();
+    /// ```
+    pub fn new() -> Self {
+        Table::default()
+    }
+
+    /// Add a row of cells to the table.
+    ///
+    /// # Arguments
+    ///
+    /// * `cells` - Cells of the row, one per column.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use simplegen::Table;
+    ///
+    /// let mut table = Table::new();
+    /// table.row(&["const A", "= 1;"]).row(&["const B", "= 2;"]);
+    /// ```
+    pub fn row(&mut self, cells: &[&str]) -> &mut Self {
+        self.rows
+            .push(cells.iter().map(|cell| cell.to_string()).collect());
+        self
+    }
+
+    /// Render each row of the table as a string with its columns aligned.
+    pub(crate) fn render(&self) -> Vec<String> {
+        let mut widths: Vec<usize> = Vec::new();
+        for row in &self.rows {
+            for (column, cell) in row.iter().enumerate() {
+                let width = text_width(cell);
+                match widths.get_mut(column) {
+                    Some(max) => *max = (*max).max(width),
+                    None => widths.push(width),
+                }
+            }
+        }
+
+        self.rows
+            .iter()
+            .map(|row| {
+                let mut line = String::new();
+                for (column, cell) in row.iter().enumerate() {
+                    if column > 0 {
+                        line.push(' ');
+                    }
+                    line.push_str(cell);
+                    if column + 1 < row.len() {
+                        let padding = widths[column] - text_width(cell);
+                        line.push_str(&" ".repeat(padding));
+                    }
+                }
+                line
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::code_buffer::CodeBuffer;
+    use crate::table::Table;
+
+    #[test]
+    fn table_should_align_columns() {
+        let mut table = Table::new();
+        table.row(&["const A", "= 1;"]);
+        table.row(&["const LONG", "= 22;"]);
+        table.row(&["const BC", "= 3;"]);
+
+        let mut printer = CodeBuffer::new(4);
+        printer.indent_right();
+        printer.println_table(&table);
+        let actual = printer.to_string();
+
+        let expected = "    const A    = 1;\n    const LONG = 22;\n    const BC   = 3;";
+        assert_eq!(expected, actual);
+    }
+
+    #[test]
+    fn table_should_not_pad_missing_cells() {
+        let mut table = Table::new();
+        table.row(&["a", "b", "c"]);
+        table.row(&["long"]);
+        table.row(&["d", "longer", "e"]);
+
+        let actual = table.render();
+
+        assert_eq!(vec!["a    b      c", "long", "d    longer e"], actual);
+    }
+}
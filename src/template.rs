@@ -0,0 +1,371 @@
+use alloc::collections::BTreeMap;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+/// A [TemplateContext] holds the values that a template is filled in with by
+/// [CodeBuffer::render_template()](crate::CodeBuffer::render_template).
+///
+/// Values replace `{key}` placeholders, and lists are repeated over by
+/// `{{#each key}}` blocks. An `{{#if key}}` block is kept if `key` is set to
+/// a value or list that isn't empty.
+///
+/// # Example
+/// ```
+/// use simplegen::{CodeBuffer, TemplateContext};
+///
+/// let mut ctx = TemplateContext::new();
+/// ctx.set("name", "Point").set_list("fields", ["x", "y"]);
+///
+/// let mut buffer = CodeBuffer::default();
+/// buffer.render_template(
+///     "struct {name} {\n{{#each fields}}\n    {this}: f64,\n{{/each}}\n}",
+///     &ctx,
+/// );
+///
+/// assert_eq!(
+///     "struct Point {\n    x: f64,\n    y: f64,\n}",
+///     buffer.to_string()
+/// );
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TemplateContext {
+    /// Value of each `{key}` placeholder, by key.
+    values: BTreeMap<String, String>,
+    /// Items that each `{{#each key}}` block is repeated for, by key.
+    lists: BTreeMap<String, Vec<String>>,
+}
+
+impl TemplateContext {
+    /// Create a new, empty `TemplateContext`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use simplegen::TemplateContext;
+    ///
+    /// let ctx = TemplateContext::new();
+    /// ```
+    pub fn new() -> Self {
+        TemplateContext::default()
+    }
+
+    /// Set the value that `{key}` placeholders are replaced by.
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - Name of the placeholder.
+    /// * `value` - String to replace the placeholder with.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use simplegen::TemplateContext;
+    ///
+    /// let mut ctx = TemplateContext::new();
+    /// ctx.set("name", "Point").set("derive", "Debug");
+    /// ```
+    pub fn set(&mut self, key: &str, value: &str) -> &mut Self {
+        self.values.insert(key.to_string(), value.to_string());
+        self
+    }
+
+    /// Set the items that `{{#each key}}` blocks are repeated for. Inside the
+    /// block, `{this}` is replaced by the current item.
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - Name of the list.
+    /// * `items` - Items to repeat the block for, in order.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use simplegen::TemplateContext;
+    ///
+    /// let mut ctx = TemplateContext::new();
+    /// ctx.set_list("variants", ["Red", "Green", "Blue"]);
+    /// ```
+    pub fn set_list<I, S>(&mut self, key: &str, items: I) -> &mut Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let items = items
+            .into_iter()
+            .map(|item| item.as_ref().to_string())
+            .collect();
+        self.lists.insert(key.to_string(), items);
+        self
+    }
+
+    /// Get the value of a placeholder. `this` is the current item of the
+    /// innermost `{{#each}}` block, if there is one.
+    fn value<'a>(&'a self, key: &str, item: Option<&'a str>) -> Option<&'a str> {
+        match item {
+            Some(item) if key == "this" => Some(item),
+            _ => self.values.get(key).map(String::as_str),
+        }
+    }
+
+    /// Whether an `{{#if key}}` block is kept.
+    fn is_set(&self, key: &str, item: Option<&str>) -> bool {
+        self.value(key, item).is_some_and(|value| !value.is_empty())
+            || self.lists.get(key).is_some_and(|list| !list.is_empty())
+    }
+}
+
+/// Part of a parsed template.
+enum Node<'t> {
+    /// Text, which may contain `{key}` placeholders.
+    Text(&'t str),
+    /// `{{#if key}}` block, kept if `key` is set.
+    If(&'t str, Vec<Node<'t>>),
+    /// `{{#each key}}` block, repeated for each item in the list `key`.
+    Each(&'t str, Vec<Node<'t>>),
+}
+
+/// A block tag in a template.
+enum Tag<'t> {
+    /// `{{#if key}}`.
+    If(&'t str),
+    /// `{{#each key}}`.
+    Each(&'t str),
+    /// `{{/if}}`.
+    EndIf,
+    /// `{{/each}}`.
+    EndEach,
+}
+
+/// Render a template with `{{#if}}` and `{{#each}}` blocks and `{key}`
+/// placeholders. A block tag that is alone on its line is removed along with
+/// the line, so tags don't leave blank lines behind.
+///
+/// # Panics
+///
+/// Panics if a block is never closed, or is closed by the wrong tag.
+pub(crate) fn render_template(template: &str, ctx: &TemplateContext) -> String {
+    let mut out = String::with_capacity(template.len());
+    render_nodes(&parse_template(template), ctx, None, &mut out);
+    out
+}
+
+/// Split a template into text and blocks.
+fn parse_template(template: &str) -> Vec<Node<'_>> {
+    let mut nodes = Vec::new();
+    let mut open_blocks: Vec<(Tag<'_>, &str, Vec<Node<'_>>)> = Vec::new();
+    let mut text_start = 0;
+    let mut search = 0;
+
+    while let Some(found) = template[search..].find("{{") {
+        let open = search + found;
+        let parsed = template[open..].find("}}").and_then(|close| {
+            let tag = parse_tag(&template[open + 2..open + close])?;
+            Some((tag, open + close + 2))
+        });
+        let (tag, end) = match parsed {
+            Some(parsed) => parsed,
+            None => {
+                search = open + 1;
+                continue;
+            }
+        };
+
+        let (text_end, next) = tag_bounds(template, open, end);
+        if text_end > text_start {
+            nodes.push(Node::Text(&template[text_start..text_end]));
+        }
+        text_start = next;
+        search = next;
+
+        let raw = &template[open..end];
+        match tag {
+            Tag::If(_) | Tag::Each(_) => {
+                open_blocks.push((tag, raw, core::mem::take(&mut nodes)));
+            }
+            Tag::EndIf | Tag::EndEach => {
+                let (open_tag, open_raw, outer) = open_blocks
+                    .pop()
+                    .unwrap_or_else(|| panic!("`{}` doesn't close any block", raw));
+                let body = core::mem::replace(&mut nodes, outer);
+                let node = match (open_tag, tag) {
+                    (Tag::If(key), Tag::EndIf) => Node::If(key, body),
+                    (Tag::Each(key), Tag::EndEach) => Node::Each(key, body),
+                    _ => panic!("`{}` can't close `{}`", raw, open_raw),
+                };
+                nodes.push(node);
+            }
+        }
+    }
+
+    if let Some((_, raw, _)) = open_blocks.last() {
+        panic!("`{}` is never closed", raw);
+    }
+    if text_start < template.len() {
+        nodes.push(Node::Text(&template[text_start..]));
+    }
+    nodes
+}
+
+/// Parse the contents of a `{{...}}` tag, if it's a block tag.
+fn parse_tag(tag: &str) -> Option<Tag<'_>> {
+    if let Some(key) = tag.strip_prefix("#if ") {
+        Some(Tag::If(key.trim()))
+    } else if let Some(key) = tag.strip_prefix("#each ") {
+        Some(Tag::Each(key.trim()))
+    } else {
+        match tag {
+            "/if" => Some(Tag::EndIf),
+            "/each" => Some(Tag::EndEach),
+            _ => None,
+        }
+    }
+}
+
+/// Get where the text before the tag from `open` to `end` stops, and where
+/// the text after it starts. If the tag is alone on its line, apart from
+/// whitespace, the whole line is left out.
+fn tag_bounds(template: &str, open: usize, end: usize) -> (usize, usize) {
+    let line_start = template[..open].rfind('\n').map_or(0, |index| index + 1);
+    let line_end = template[end..]
+        .find('\n')
+        .map_or(template.len(), |index| end + index + 1);
+
+    let alone =
+        template[line_start..open].trim().is_empty() && template[end..line_end].trim().is_empty();
+    if alone {
+        (line_start, line_end)
+    } else {
+        (open, end)
+    }
+}
+
+/// Render parsed nodes, with `item` as the current item of the innermost
+/// `{{#each}}` block.
+fn render_nodes(nodes: &[Node<'_>], ctx: &TemplateContext, item: Option<&str>, out: &mut String) {
+    for node in nodes {
+        match node {
+            Node::Text(text) => out.push_str(&fill_placeholders(text, |key| ctx.value(key, item))),
+            Node::If(key, body) => {
+                if ctx.is_set(key, item) {
+                    render_nodes(body, ctx, item, out);
+                }
+            }
+            Node::Each(key, body) => {
+                for each_item in ctx.lists.get(*key).into_iter().flatten() {
+                    render_nodes(body, ctx, Some(each_item), out);
+                }
+            }
+        }
+    }
+}
+
+/// Replace each `{key}` placeholder in a template with the value `lookup`
+/// returns for `key`. Placeholders that `lookup` has no value for, and braces
+/// that don't form a placeholder, are left as they are.
+pub(crate) fn fill_placeholders<'a, F>(template: &str, lookup: F) -> String
+where
+    F: Fn(&str) -> Option<&'a str>,
+{
+    let mut out = String::with_capacity(template.len());
+    let mut rest = template;
+
+    while let Some(open) = rest.find('{') {
+        out.push_str(&rest[..open]);
+        let after_open = &rest[open + 1..];
+        match after_open.find(['{', '}']) {
+            Some(close) if after_open[close..].starts_with('}') => {
+                let key = &after_open[..close];
+                match lookup(key) {
+                    Some(value) => out.push_str(value),
+                    None => out.push_str(&rest[open..open + close + 2]),
+                }
+                rest = &after_open[close + 1..];
+            }
+            _ => {
+                out.push('{');
+                rest = after_open;
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::template::{fill_placeholders, render_template, TemplateContext};
+
+    fn lookup(key: &str) -> Option<&'static str> {
+        match key {
+            "name" => Some("Parser"),
+            "field" => Some("input"),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn should_replace_known_placeholders() {
+        let actual = fill_placeholders("struct {name} { {field}: String }", lookup);
+
+        assert_eq!("struct Parser { input: String }", actual);
+    }
+
+    #[test]
+    fn should_keep_unknown_placeholders_and_stray_braces() {
+        let actual = fill_placeholders("{ {unknown} {name {{name}} }", lookup);
+
+        assert_eq!("{ {unknown} {name {Parser} }", actual);
+    }
+
+    #[test]
+    fn should_keep_if_block_only_when_key_is_set() {
+        let mut ctx = TemplateContext::new();
+        ctx.set("derive", "Debug").set("empty", "");
+
+        let actual = render_template(
+            "{{#if derive}}a{{/if}}{{#if empty}}b{{/if}}{{#if x}}c{{/if}}",
+            &ctx,
+        );
+
+        assert_eq!("a", actual);
+    }
+
+    #[test]
+    fn each_should_repeat_block_with_this_as_item() {
+        let mut ctx = TemplateContext::new();
+        ctx.set("ty", "u8").set_list("names", ["a", "b"]);
+
+        let actual = render_template("{{#each names}}{this}: {ty}, {{/each}}", &ctx);
+
+        assert_eq!("a: u8, b: u8, ", actual);
+    }
+
+    #[test]
+    fn should_remove_lines_of_standalone_tags() {
+        let mut ctx = TemplateContext::new();
+        ctx.set_list("names", ["a", "b"]);
+
+        let actual = render_template("[\n  {{#each names}}\n  {this}\n  {{/each}}\n]", &ctx);
+
+        assert_eq!("[\n  a\n  b\n]", actual);
+    }
+
+    #[test]
+    fn should_keep_tags_that_are_not_blocks() {
+        let actual = render_template("{{name}} {{#unless x}}", &TemplateContext::new());
+
+        assert_eq!("{{name}} {{#unless x}}", actual);
+    }
+
+    #[test]
+    #[should_panic(expected = "`{{#if a}}` is never closed")]
+    fn should_panic_on_unclosed_block() {
+        render_template("{{#if a}}", &TemplateContext::new());
+    }
+
+    #[test]
+    #[should_panic(expected = "`{{/each}}` can't close `{{#if a}}`")]
+    fn should_panic_on_mismatched_block() {
+        render_template("{{#if a}}{{/each}}", &TemplateContext::new());
+    }
+}
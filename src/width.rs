@@ -0,0 +1,19 @@
+/// Get the width of text in columns, for aligning and wrapping lines.
+///
+/// Widths are counted in characters rather than bytes, so accented letters
+/// and box-drawing characters take up one column like ASCII characters do.
+pub(crate) fn text_width(text: &str) -> usize {
+    text.chars().count()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::width::text_width;
+
+    #[test]
+    fn should_count_characters_rather_than_bytes() {
+        assert_eq!(4, text_width("café"));
+        assert_eq!(4, text_width("│   "));
+        assert_eq!(0, text_width(""));
+    }
+}
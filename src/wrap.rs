@@ -0,0 +1,74 @@
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use crate::width::text_width;
+
+/// Split text into lines of at most `width` characters, breaking only on
+/// whitespace. A word longer than `width` is put on a line of its own rather
+/// than being broken. Text with no words produces no lines.
+pub(crate) fn wrap_words(text: &str, width: usize) -> Vec<String> {
+    wrap_words_hanging(text, width, width)
+}
+
+/// Split text into lines like [wrap_words], but allow the first line to have a
+/// different width from the lines that follow it.
+pub(crate) fn wrap_words_hanging(text: &str, first_width: usize, width: usize) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut line = String::new();
+    let mut line_width = 0;
+
+    for word in text.split_whitespace() {
+        let word_width = text_width(word);
+        let max_width = if lines.is_empty() { first_width } else { width };
+        if line.is_empty() {
+            line.push_str(word);
+            line_width = word_width;
+        } else if line_width + 1 + word_width <= max_width {
+            line.push(' ');
+            line.push_str(word);
+            line_width += 1 + word_width;
+        } else {
+            lines.push(core::mem::take(&mut line));
+            line.push_str(word);
+            line_width = word_width;
+        }
+    }
+
+    if !line.is_empty() {
+        lines.push(line);
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::wrap::{wrap_words, wrap_words_hanging};
+
+    #[test]
+    fn should_wrap_on_whitespace() {
+        let actual = wrap_words("the quick brown fox", 10);
+
+        assert_eq!(vec!["the quick", "brown fox"], actual);
+    }
+
+    #[test]
+    fn should_not_break_long_words() {
+        let actual = wrap_words("a abcdefghij b", 5);
+
+        assert_eq!(vec!["a", "abcdefghij", "b"], actual);
+    }
+
+    #[test]
+    fn hanging_should_use_first_width_for_first_line_only() {
+        let actual = wrap_words_hanging("a b c d e", 5, 3);
+
+        assert_eq!(vec!["a b c", "d e"], actual);
+    }
+
+    #[test]
+    fn should_produce_no_lines_for_blank_text() {
+        let actual = wrap_words("   ", 5);
+
+        assert!(actual.is_empty());
+    }
+}